@@ -7,28 +7,52 @@ use tauri::AppHandle;
 use tauri_specta::Event;
 use tokio::task;
 
+pub mod passwords;
+
+/// Why a launch failed. Tagged so the UI can tell a missing password apart from
+/// everything else and prompt for one.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type)]
+#[serde(tag = "kind", content = "message")]
+pub enum LaunchError {
+    /// The server wants a password and we don't have one saved. Holds the server's query address.
+    PasswordRequired(String),
+    Failed(String),
+}
+
+impl From<String> for LaunchError {
+    fn from(message: String) -> Self {
+        LaunchError::Failed(message)
+    }
+}
+
+impl From<&str> for LaunchError {
+    fn from(message: &str) -> Self {
+        LaunchError::Failed(message.to_string())
+    }
+}
+
 #[tauri::command]
 #[specta::specta]
-pub async fn dayz_launch_vanilla(server: Server32, app_handle: AppHandle) -> Result<(), String> {
+pub async fn dayz_launch_vanilla(
+    server: Server32,
+    app_handle: AppHandle,
+) -> Result<(), LaunchError> {
     // Grab the steam client
-    let client = client::get_client().await;
-    if client.is_none() {
-        return Err("Steam client not initialized".to_string());
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("Steam client not initialized".into());
     }
-    let client = client.unwrap();
-    let apps = client.apps();
+    let backend = backend.unwrap();
 
     // Check if DayZ is installed
-    if !apps.is_app_installed(steamworks::AppId(221100)) {
-        return Err("DayZ not installed".to_string());
+    if !backend.is_app_installed(221100) {
+        return Err("DayZ not installed".into());
     }
-    let path = apps.app_install_dir(steamworks::AppId(221100));
+    let path = backend.app_install_dir(221100);
 
     // Make sure there is not mod list
     if server.mod_list.is_some() {
-        return Err(
-            "Mods are not supported for vanilla DayZ, try launching with mods!".to_string(),
-        );
+        return Err("Mods are not supported for vanilla DayZ, try launching with mods!".into());
     }
 
     // Grab game addr
@@ -36,14 +60,19 @@ pub async fn dayz_launch_vanilla(server: Server32, app_handle: AppHandle) -> Res
         + ":"
         + &server.game_port.to_string();
 
+    let password = launch_password(&server, &app_handle)?;
+
     // Spawn a task to launch DayZ and emit a shutdown event when it closes
     task::spawn(async move {
         let handle = app_handle.clone();
 
-        Command::new(path + "\\DayZ_BE.exe")
-            .arg("-connect=".to_owned() + &addr)
-            .status()
-            .expect("Failed to start DayZ");
+        let mut command = Command::new(path + "\\DayZ_BE.exe");
+        command.arg("-connect=".to_owned() + &addr);
+        if let Some(password) = password {
+            command.arg("-password=".to_owned() + &password);
+        }
+
+        command.status().expect("Failed to start DayZ");
 
         DayzShutdownEvent {}
             .emit(&handle)
@@ -55,36 +84,37 @@ pub async fn dayz_launch_vanilla(server: Server32, app_handle: AppHandle) -> Res
 
 #[tauri::command]
 #[specta::specta]
-pub async fn dayz_launch_modded(server: Server32, app_handle: AppHandle) -> Result<(), String> {
+pub async fn dayz_launch_modded(
+    server: Server32,
+    app_handle: AppHandle,
+) -> Result<(), LaunchError> {
     // Grab the steam client
-    let client = client::get_client().await;
-    if client.is_none() {
-        return Err("Steam client not initialized".to_string());
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("Steam client not initialized".into());
     }
-    let client = client.unwrap();
-    let apps = client.apps();
+    let backend = backend.unwrap();
 
     // Check if DayZ is installed
-    if !apps.is_app_installed(steamworks::AppId(221100)) {
-        return Err("DayZ not installed".to_string());
+    if !backend.is_app_installed(221100) {
+        return Err("DayZ not installed".into());
     }
-    let path = apps.app_install_dir(steamworks::AppId(221100));
+    let path = backend.app_install_dir(221100);
 
     // Make sure there IS mod list
-    let mod_list = server.mod_list;
+    let mod_list = server.mod_list.clone();
     if mod_list.is_none() {
-        return Err("You are trying to connect to a server with no mod list!".to_string());
+        return Err("You are trying to connect to a server with no mod list!".into());
     }
     let mod_list = mod_list.unwrap();
 
     // Collect mod paths
-    let ugc = client.ugc();
     let mut mod_paths: Vec<String> = Vec::new();
     for dayz_mod in mod_list {
-        let mod_id = steamworks::PublishedFileId(dayz_mod.workshop_id.parse().unwrap());
-        let path = ugc.item_install_info(mod_id);
+        let mod_id = dayz_mod.workshop_id.parse().unwrap();
+        let path = backend.item_install_info(mod_id);
         if path.is_none() {
-            return Err("Failed to get mod path".to_string());
+            return Err("Failed to get mod path".into());
         }
 
         mod_paths.push(path.unwrap().folder.as_str().to_owned());
@@ -102,15 +132,19 @@ pub async fn dayz_launch_modded(server: Server32, app_handle: AppHandle) -> Resu
         + ":"
         + &server.game_port.to_string();
 
+    let password = launch_password(&server, &app_handle)?;
+
     // Spawn a task to launch DayZ and emit a shutdown event when it closes
     task::spawn(async move {
         let handle = app_handle.clone();
 
-        Command::new(path + "\\DayZ_BE.exe")
-            .arg(mod_param)
-            .arg("-connect=".to_owned() + &addr)
-            .status()
-            .expect("Failed to start DayZ");
+        let mut command = Command::new(path + "\\DayZ_BE.exe");
+        command.arg(mod_param).arg("-connect=".to_owned() + &addr);
+        if let Some(password) = password {
+            command.arg("-password=".to_owned() + &password);
+        }
+
+        command.status().expect("Failed to start DayZ");
 
         DayzShutdownEvent {}
             .emit(&handle)
@@ -120,19 +154,38 @@ pub async fn dayz_launch_modded(server: Server32, app_handle: AppHandle) -> Resu
     Ok(())
 }
 
+/// Grabs the saved password for a server. Only servers that need one look at the store,
+/// if there's none saved (or the store can't be read) we error out with `PasswordRequired`
+/// so the UI can ask for it.
+fn launch_password(
+    server: &Server32,
+    app_handle: &AppHandle,
+) -> Result<Option<String>, LaunchError> {
+    if !server.password_required {
+        return Ok(None);
+    }
+
+    let password = passwords::get(app_handle, &server.addr)
+        .map_err(|e| format!("Couldn't read saved passwords: {}", e))?;
+    if password.is_none() {
+        return Err(LaunchError::PasswordRequired(server.addr.clone()));
+    }
+
+    Ok(password)
+}
+
 /// Gets the player list from a DayZ server. User to be actively connected to the server.
 #[tauri::command]
 #[specta::specta]
 pub async fn dayz_get_playerlist(server: Server32) -> Result<Vec<Player>, String> {
     // Grab the steam client
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("Steam client not initialized".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Grab steam id
-    let friends = client.friends();
     let steam_id: u64 = server
         .steam_id
         .parse()
@@ -140,19 +193,13 @@ pub async fn dayz_get_playerlist(server: Server32) -> Result<Vec<Player>, String
 
     // Get players on the server
     let mut users: Vec<Player> = Vec::new();
-    let server_steam_connection =
-        friends.get_friends_from_source(steamworks::SteamId::from_raw(steam_id));
+    let server_steam_connection = backend.players_from_source(steam_id);
     for user in server_steam_connection {
-        let name = user.name();
-        let nick_name = user.nick_name();
-        let avatar = user.medium_avatar();
-        let steam_id = user.id().raw();
-
         let user = Player {
-            name,
-            steam_id: steam_id.to_string(),
-            nick_name: nick_name.unwrap_or("".to_string()),
-            avatar: avatar.unwrap_or(Vec::new()),
+            name: user.name,
+            steam_id: user.steam_id.to_string(),
+            nick_name: user.nick_name.unwrap_or("".to_string()),
+            avatar: user.avatar.unwrap_or(Vec::new()),
             is_banned: false,
         };
 
@@ -0,0 +1,246 @@
+//! Saved server passwords. Kept in an AES-256-GCM encrypted file in the app data dir,
+//! so they never sit on disk in plain text. The key lives in the OS credential store
+//! (Windows Credential Manager, macOS Keychain, the kernel keyring on Linux), so copying the
+//! app data dir alone doesn't give the passwords away.
+
+use anyhow::anyhow;
+use anyhow::Result;
+use ring::aead::Aad;
+use ring::aead::LessSafeKey;
+use ring::aead::Nonce;
+use ring::aead::UnboundKey;
+use ring::aead::AES_256_GCM;
+use ring::aead::NONCE_LEN;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+
+const STORE_FILE: &str = "server_passwords.bin";
+/// Where older versions kept the key, moved into the credential store on first use.
+const LEGACY_KEY_FILE: &str = "server_passwords.key";
+const KEY_LEN: usize = 32;
+const KEYRING_USER: &str = "server_passwords";
+
+/// The store is there, but can't be decrypted or parsed. Nothing in it can be recovered.
+#[derive(Debug)]
+struct CorruptStore;
+
+impl fmt::Display for CorruptStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Password store is corrupt")
+    }
+}
+
+impl std::error::Error for CorruptStore {}
+
+/// Saves the password for a server, replacing any saved one.
+/// `@param: addr` - The server's query address.
+#[tauri::command]
+#[specta::specta]
+pub async fn dayz_set_server_password(
+    addr: String,
+    password: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let mut passwords = match load(&app_handle) {
+        Ok(passwords) => passwords,
+        // Can't be recovered, start over rather than locking the user out. Anything else
+        // (e.g. a locked keychain) might go away, so we leave the store alone
+        Err(e) if e.is::<CorruptStore>() => HashMap::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+    passwords.insert(addr, password);
+    save(&app_handle, &passwords).map_err(|e| e.to_string())
+}
+
+/// Forgets the saved password for a server.
+/// `@param: addr` - The server's query address.
+#[tauri::command]
+#[specta::specta]
+pub async fn dayz_remove_server_password(
+    addr: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let mut passwords = load(&app_handle).map_err(|e| e.to_string())?;
+    if passwords.remove(&addr).is_some() {
+        save(&app_handle, &passwords).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Whether we have a password saved for a server. Passwords never go back to the frontend.
+/// `@param: addr` - The server's query address.
+#[tauri::command]
+#[specta::specta]
+pub async fn dayz_has_server_password(addr: String, app_handle: AppHandle) -> Result<bool, String> {
+    let passwords = load(&app_handle).map_err(|e| e.to_string())?;
+    Ok(passwords.contains_key(&addr))
+}
+
+/// Returns the saved password for a server, if there is one.
+/// A corrupt store counts as nothing saved, the user has to enter it again.
+pub fn get(app_handle: &AppHandle, addr: &str) -> Result<Option<String>> {
+    match load(app_handle) {
+        Ok(mut passwords) => Ok(passwords.remove(addr)),
+        Err(e) if e.is::<CorruptStore>() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Decrypts the password store, an empty map if nothing is saved yet.
+fn load(app_handle: &AppHandle) -> Result<HashMap<String, String>> {
+    let store_path = store_dir(app_handle)?.join(STORE_FILE);
+    if !store_path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let data = fs::read(store_path)?;
+    let key = load_key(app_handle)?;
+    Ok(decrypt(&key, &data)?)
+}
+
+/// Encrypts and writes the password store.
+fn save(app_handle: &AppHandle, passwords: &HashMap<String, String>) -> Result<()> {
+    let key = load_key(app_handle)?;
+    let data = encrypt(&key, passwords)?;
+    fs::write(store_dir(app_handle)?.join(STORE_FILE), data)?;
+    Ok(())
+}
+
+/// Opens a store written by `encrypt`.
+fn decrypt(key: &LessSafeKey, data: &[u8]) -> Result<HashMap<String, String>, CorruptStore> {
+    // Stored as nonce || ciphertext || tag
+    if data.len() < NONCE_LEN {
+        return Err(CorruptStore);
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| CorruptStore)?;
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::empty(), &mut in_out)
+        .map_err(|_| CorruptStore)?;
+
+    serde_json::from_slice(plaintext).map_err(|_| CorruptStore)
+}
+
+/// Seals the passwords with a fresh nonce every time.
+fn encrypt(key: &LessSafeKey, passwords: &HashMap<String, String>) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate nonce"))?;
+
+    let mut in_out = serde_json::to_vec(passwords)?;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut in_out,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt password store"))?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&in_out);
+    Ok(data)
+}
+
+/// Loads the store key from the credential store, generating one on first use.
+fn load_key(app_handle: &AppHandle) -> Result<LessSafeKey> {
+    let entry = keyring::Entry::new(&app_handle.config().identifier, KEYRING_USER)?;
+    let legacy_path = store_dir(app_handle)?.join(LEGACY_KEY_FILE);
+
+    let key_bytes = match entry.get_password() {
+        Ok(encoded) => decode_key(&encoded)?,
+        Err(keyring::Error::NoEntry) => {
+            let key_bytes = match legacy_path.exists() {
+                true => fs::read(&legacy_path)?,
+                false => {
+                    let mut key_bytes = vec![0u8; KEY_LEN];
+                    SystemRandom::new()
+                        .fill(&mut key_bytes)
+                        .map_err(|_| anyhow!("Failed to generate password store key"))?;
+                    key_bytes
+                }
+            };
+            entry.set_password(&encode_key(&key_bytes))?;
+            key_bytes
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // The key is safely in the credential store now, don't leave a copy on disk
+    if legacy_path.exists() {
+        fs::remove_file(&legacy_path)?;
+    }
+
+    let key = UnboundKey::new(&AES_256_GCM, &key_bytes)
+        .map_err(|_| anyhow!("Password store key is corrupt"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Credential stores hold strings, so the key goes in as hex.
+fn encode_key(key_bytes: &[u8]) -> String {
+    key_bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>> {
+    if encoded.len() != KEY_LEN * 2 || !encoded.is_ascii() {
+        return Err(anyhow!("Password store key is corrupt"));
+    }
+
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&encoded[i..i + 2], 16)
+                .map_err(|_| anyhow!("Password store key is corrupt"))
+        })
+        .collect()
+}
+
+fn store_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+    let dir = app_handle.path().app_data_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &[byte; KEY_LEN]).unwrap())
+    }
+
+    #[test]
+    fn round_trips() {
+        let passwords = HashMap::from([("1.1.1.1:2303".to_string(), "hunter2".to_string())]);
+        let data = encrypt(&key(1), &passwords).unwrap();
+
+        assert!(!data.windows(7).any(|w| w == b"hunter2"));
+        assert_eq!(decrypt(&key(1), &data).unwrap(), passwords);
+    }
+
+    #[test]
+    fn unreadable_store_is_corrupt() {
+        let data = encrypt(&key(1), &HashMap::new()).unwrap();
+
+        assert!(decrypt(&key(2), &data).is_err());
+        assert!(decrypt(&key(1), &data[..NONCE_LEN - 1]).is_err());
+
+        let mut tampered = data.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&key(1), &tampered).is_err());
+    }
+}
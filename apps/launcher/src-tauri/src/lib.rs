@@ -1,6 +1,12 @@
 use crate::dayz::DayzShutdownEvent;
+use crate::query::alerts::FavoriteAlertEvent;
+use crate::query::diff::ServerListDiffEvent;
+use crate::steam::installed::InstalledModsListedEvent;
+use crate::steam::supervisor::SteamStatusEvent;
 use crate::steam::ActiveDownloadProgressEvent;
+use crate::steam::ModDownloadProgressEvent;
 use crate::steam::ModInfoFoundEvent;
+use crate::steam::ModQueueStateEvent;
 use tauri::Manager;
 use window_vibrancy::apply_acrylic;
 
@@ -33,12 +39,20 @@ pub fn run() {
                 dayz::dayz_launch_modded,
                 dayz::dayz_get_playerlist,
                 dayz::dayz_get_player_ban_status,
+                dayz::passwords::dayz_set_server_password,
+                dayz::passwords::dayz_remove_server_password,
+                dayz::passwords::dayz_has_server_password,
                 steam::mdq_clear,
                 steam::mdq_add_mod,
                 steam::mdq_remove_mod,
+                steam::mdq_cancel_mod,
+                steam::mdq_move_mod,
+                steam::mdq_pause_mod,
+                steam::mdq_resume_mod,
                 steam::mdq_get_active_download_progress,
                 steam::mdq_get_active_download_id,
                 steam::mdq_start_daemon,
+                steam::mdq_list,
                 steam::steam_remove_mod,
                 steam::steam_remove_mod_forcefully,
                 steam::steam_fix_mod,
@@ -52,17 +66,45 @@ pub fn run() {
                 steam::steam_start_daemon,
                 steam::steam_mount_api,
                 steam::steam_unmount_api,
+                steam::steam_remount_api,
+                steam::supervisor::steam_start_supervisor,
+                steam::supervisor::steam_get_status,
+                steam::updates::steam_get_outdated_mods,
+                steam::updates::steam_update_all_mods,
+                steam::workshop::steam_search_workshop,
                 query::get_server_info,
+                query::details::get_server_details,
+                query::custom::query_add_custom_server,
+                query::custom::query_remove_custom_server,
+                query::custom::query_list_custom_servers,
+                query::lan::query_discover_lan,
+                query::settings::query_get_settings,
+                query::settings::query_set_settings,
+                query::geo::query_filter_by_location,
+                query::ping::query_measure_ping,
+                query::ping::query_set_ping_favorites,
+                query::ping::query_start_ping_watcher,
+                query::diff::query_get_recent_diffs,
+                query::alerts::alerts_set_favorite,
+                query::alerts::alerts_remove_favorite,
+                query::alerts::alerts_list_favorites,
+                query::alerts::alerts_start_watcher,
                 query::get_server_list,
-                query::update_server_info_semaphore,
-                query::destroy_server_info_semaphore,
-                query::fetch,
+                query::query_set_max_concurrency,
+                query::query_cancel,
+                query::fetcher::fetch,
                 updater::check_for_updates,
             ])
             .events(tauri_specta::collect_events![
                 ActiveDownloadProgressEvent,
                 DayzShutdownEvent,
-                ModInfoFoundEvent
+                FavoriteAlertEvent,
+                InstalledModsListedEvent,
+                ModDownloadProgressEvent,
+                ModInfoFoundEvent,
+                ModQueueStateEvent,
+                ServerListDiffEvent,
+                SteamStatusEvent
             ]);
 
         #[cfg(debug_assertions)] // <- Only export on non-release builds
@@ -77,6 +119,7 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .invoke_handler(typesafe_handler)
         .setup(|app| {
@@ -87,6 +130,9 @@ pub fn run() {
                 .expect("Unsupported platform! 'apply_acrylic' is only supported on Windows");
 
             register_typesafe_events(app);
+
+            // Pick the download queue back up from last time
+            tauri::async_runtime::spawn(steam::mdq_restore(app.handle().clone()));
             Ok(())
         })
         .run(tauri::generate_context!())
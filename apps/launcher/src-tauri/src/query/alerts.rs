@@ -0,0 +1,318 @@
+//! Favorite server alerts. The watcher polls favorites with alert rules on its own
+//! schedule, and raises a desktop notification plus a `FavoriteAlertEvent` when a rule fires.
+
+use super::rules;
+use super::scheduler::QueryError;
+use super::scheduler::QueryPriority;
+use super::scheduler::QUERY_SCHEDULER;
+use a2s::A2SClient;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+use tauri_specta::Event;
+use tokio::sync::Mutex;
+use tokio::sync::RwLock;
+use tokio::task;
+use tokio::time;
+
+/// How often the watcher checks on favorites.
+const ALERT_POLL_SECS: u64 = 60;
+
+lazy_static! {
+    static ref IS_ALERT_WATCHER_RUNNING: Arc<RwLock<bool>> = Arc::new(RwLock::new(false));
+    /// What each favorite looked like last time we checked, keyed by query address.
+    static ref WATCH_STATE: Arc<Mutex<HashMap<String, WatchState>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// Something worth telling the user about a favorite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum AlertRule {
+    /// Player count dropped below N, i.e. a slot opened up.
+    PlayersBelow(u32),
+    /// The server answers again after being unreachable.
+    BackOnline,
+    /// The server version changed, usually a wipe or game update.
+    VersionChanged,
+    /// The server's mod list changed.
+    ModsUpdated,
+}
+
+/// Alert rules for one favorite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct FavoriteAlerts {
+    /// The server's query address.
+    pub addr: String,
+    /// Shown in notifications, so we don't have to wait for a query to name the server.
+    pub name: String,
+    pub rules: Vec<AlertRule>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
+pub struct FavoriteAlertEvent {
+    pub addr: String,
+    pub name: String,
+    pub rule: AlertRule,
+    pub message: String,
+}
+
+/// Last observation of a favorite.
+#[derive(Default, Debug, Clone)]
+struct WatchState {
+    online: Option<bool>,
+    /// Whether the players were below each `PlayersBelow` limit, keyed by the limit.
+    /// Missing until the first answer, so a server that's already below doesn't fire.
+    players_below: HashMap<u32, bool>,
+    version: Option<String>,
+    mods: Option<HashSet<i64>>,
+}
+
+impl WatchState {
+    /// Records the player count against `limit`, true if it just dropped below it.
+    fn dropped_below(&mut self, limit: u32, players: u32) -> bool {
+        let below = players < limit;
+        let was_below = self.players_below.insert(limit, below);
+        below && was_below == Some(false)
+    }
+}
+
+/// Sets the alert rules for a favorite, replacing any existing ones.
+/// An empty rule list stops watching the server.
+#[tauri::command]
+#[specta::specta]
+pub async fn alerts_set_favorite(
+    alerts: FavoriteAlerts,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let mut favorites = load(&app_handle).map_err(|e| e.to_string())?;
+    WATCH_STATE.lock().await.remove(&alerts.addr);
+
+    if alerts.rules.is_empty() {
+        favorites.remove(&alerts.addr);
+    } else {
+        favorites.insert(alerts.addr.clone(), alerts);
+    }
+
+    save(&app_handle, &favorites).map_err(|e| e.to_string())
+}
+
+/// Stops watching a favorite, e.g. when it gets unfavorited.
+#[tauri::command]
+#[specta::specta]
+pub async fn alerts_remove_favorite(addr: String, app_handle: AppHandle) -> Result<(), String> {
+    let mut favorites = load(&app_handle).map_err(|e| e.to_string())?;
+    WATCH_STATE.lock().await.remove(&addr);
+
+    if favorites.remove(&addr).is_some() {
+        save(&app_handle, &favorites).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn alerts_list_favorites(app_handle: AppHandle) -> Result<Vec<FavoriteAlerts>, String> {
+    let favorites = load(&app_handle).map_err(|e| e.to_string())?;
+    Ok(favorites.into_values().collect())
+}
+
+/// Starts the favorite watcher, safe to call more than once.
+#[tauri::command]
+#[specta::specta]
+pub async fn alerts_start_watcher(app_handle: AppHandle) -> Result<(), String> {
+    let is_alert_watcher_running_ref = IS_ALERT_WATCHER_RUNNING.clone();
+    let mut is_alert_watcher_running = is_alert_watcher_running_ref.write().await;
+    if *is_alert_watcher_running {
+        return Ok(());
+    }
+
+    *is_alert_watcher_running = true;
+
+    // Alert Watcher 🔔
+    // Every so often, query each favorite that has rules and compare it with what
+    // we saw last time. Rules only fire on a change, so nobody gets spammed.
+    task::spawn(async move {
+        let handle = app_handle.clone();
+
+        loop {
+            if let Err(e) = check_favorites(&handle).await {
+                println!("alert_watcher: {}", e);
+            }
+
+            time::sleep(Duration::from_secs(ALERT_POLL_SECS)).await;
+        }
+    });
+
+    Ok(())
+}
+
+async fn check_favorites(handle: &AppHandle) -> Result<()> {
+    let favorites = load(handle)?;
+    if favorites.is_empty() {
+        return Ok(());
+    }
+
+    let a2s_client = A2SClient::new().await?;
+    for favorite in favorites.values() {
+        let addr = favorite.addr.as_str();
+        let wants_mods = favorite.rules.contains(&AlertRule::ModsUpdated);
+
+        let info = QUERY_SCHEDULER
+            .run(addr, QueryPriority::Favorite, a2s_client.info(addr))
+            .await;
+        let mods = match (wants_mods, &info) {
+            (true, Ok(_)) => QUERY_SCHEDULER
+                .run(addr, QueryPriority::Favorite, a2s_client.rules_raw(addr))
+                .await
+                .ok()
+                .and_then(|raw_rules| rules::decode(&raw_rules).mod_list())
+                .map(|mods| mods.into_iter().map(|m| m.workshop_id).collect()),
+            _ => None,
+        };
+
+        let mut watch_state = WATCH_STATE.lock().await;
+        let previous = watch_state.entry(favorite.addr.clone()).or_default();
+
+        let info = match info {
+            Ok(info) => info,
+            // Somebody else cancelled this server's queries, try again next round
+            Err(QueryError::Cancelled) => continue,
+            Err(QueryError::A2S(_)) => {
+                previous.online = Some(false);
+                continue;
+            }
+        };
+
+        let mut fired = Vec::new();
+        for rule in favorite.rules.iter() {
+            let message = match rule {
+                AlertRule::PlayersBelow(limit) => {
+                    match previous.dropped_below(*limit, info.players as u32) {
+                        true => format!(
+                            "{}/{} players, a slot is open",
+                            info.players, info.max_players
+                        ),
+                        false => continue,
+                    }
+                }
+                AlertRule::BackOnline => match previous.online {
+                    Some(false) => "Server is back online".to_string(),
+                    _ => continue,
+                },
+                AlertRule::VersionChanged => match &previous.version {
+                    Some(version) if *version != info.version => {
+                        format!("Version changed from {} to {}", version, info.version)
+                    }
+                    _ => continue,
+                },
+                AlertRule::ModsUpdated => match (&previous.mods, &mods) {
+                    (Some(old), Some(new)) if old != new => "Mods were updated".to_string(),
+                    _ => continue,
+                },
+            };
+
+            fired.push(FavoriteAlertEvent {
+                addr: favorite.addr.clone(),
+                name: favorite.name.clone(),
+                rule: rule.clone(),
+                message,
+            });
+        }
+
+        previous.online = Some(true);
+        previous.version = Some(info.version.clone());
+        if mods.is_some() {
+            previous.mods = mods;
+        }
+        drop(watch_state);
+
+        for alert in fired {
+            notify(handle, &alert);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the alert to the frontend and the desktop.
+fn notify(handle: &AppHandle, alert: &FavoriteAlertEvent) {
+    println!("alert_watcher: {} - {}", alert.name, alert.message);
+
+    if let Err(e) = alert.emit(handle) {
+        println!("alert_watcher: Failed to emit alert: {}", e);
+    }
+
+    let notification = handle
+        .notification()
+        .builder()
+        .title(&alert.name)
+        .body(&alert.message)
+        .show();
+    if let Err(e) = notification {
+        println!("alert_watcher: Failed to show notification: {}", e);
+    }
+}
+
+fn load(app_handle: &AppHandle) -> Result<HashMap<String, FavoriteAlerts>> {
+    let path = alerts_path(app_handle)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn save(app_handle: &AppHandle, favorites: &HashMap<String, FavoriteAlerts>) -> Result<()> {
+    fs::write(alerts_path(app_handle)?, serde_json::to_string(favorites)?)?;
+    Ok(())
+}
+
+fn alerts_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    let dir = app_handle.path().app_config_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    Ok(dir.join("favorite_alerts.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_below_is_seeded_quietly() {
+        let mut state = WatchState::default();
+        assert!(!state.dropped_below(10, 5));
+        assert!(!state.dropped_below(10, 5));
+    }
+
+    #[test]
+    fn players_below_limits_keep_their_own_edge() {
+        let mut state = WatchState::default();
+        assert!(!state.dropped_below(10, 30));
+        assert!(!state.dropped_below(20, 30));
+
+        // Below 20 but not 10, only the 20 fires, and only once
+        assert!(state.dropped_below(20, 15));
+        assert!(!state.dropped_below(10, 15));
+        assert!(!state.dropped_below(20, 15));
+        assert!(!state.dropped_below(10, 15));
+
+        // Now below 10 too, 20 was already below so it stays quiet
+        assert!(!state.dropped_below(20, 5));
+        assert!(state.dropped_below(10, 5));
+    }
+}
@@ -0,0 +1,163 @@
+use super::keywords;
+use super::now_secs;
+use super::Server;
+use a2s::info::Info;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// The A2S player fields are a single byte, anything past this gets truncated.
+/// DayZ servers can run more slots than that, so we have to watch out for it.
+const A2S_COUNT_CAP: i64 = 255;
+
+/// Where a player count came from.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum CountSource {
+    /// The FTL API snapshot, can be a few minutes old.
+    #[default]
+    Api,
+    /// Straight from the server's A2S_INFO response.
+    A2S,
+    /// A2S hit the 255 cap, so the API filled in what A2S couldn't.
+    Mixed,
+}
+
+/// A player count, along with where it came from and how much we trust it.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerCount {
+    pub players: i64,
+    pub max_players: i64,
+    /// Login queue size, DayZ reports this in the keywords as `lqs<N>`.
+    pub queue: Option<i64>,
+    pub source: CountSource,
+    /// Unix timestamp (seconds) of when this count was taken.
+    pub updated_at: i64,
+    /// The server has more players or slots than A2S can report.
+    pub truncated: bool,
+    /// The server reported something impossible, e.g. more players than slots.
+    pub misreported: bool,
+}
+
+/// 32 Bit Player Count Data Structure (JS can't handle i64)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct PlayerCount32 {
+    pub players: i32,
+    pub max_players: i32,
+    pub queue: Option<i32>,
+    pub source: CountSource,
+    pub updated_at: u32,
+    pub truncated: bool,
+    pub misreported: bool,
+}
+
+impl PlayerCount {
+    /// Takes the count straight from an FTL API entry.
+    pub fn from_api(server: &Server) -> PlayerCount {
+        PlayerCount {
+            players: server.players,
+            max_players: server.max_players,
+            queue: keywords::login_queue(&server.game_type),
+            source: CountSource::Api,
+            updated_at: now_secs(),
+            truncated: false,
+            misreported: false,
+        }
+    }
+
+    /// Reconciles an A2S_INFO response with what we already know about the server.
+    /// `server.players` and `server.max_players` are expected to be the API values
+    /// (or the last reconciled values), they are our fallback when A2S can't be trusted.
+    pub fn reconcile(server: &Server, info: &Info) -> PlayerCount {
+        let a2s_players = info.players as i64;
+        let a2s_max_players = info.max_players as i64;
+        let queue = info
+            .extended_server_info
+            .keywords
+            .as_deref()
+            .and_then(keywords::login_queue);
+
+        // Anything sitting on the cap, or an API value past it, means A2S got truncated
+        let truncated = a2s_players >= A2S_COUNT_CAP
+            || a2s_max_players >= A2S_COUNT_CAP
+            || server.max_players > A2S_COUNT_CAP;
+
+        // Zero slots, or more players than slots, is just the server lying to us
+        let misreported = a2s_max_players == 0 || (!truncated && a2s_players > a2s_max_players);
+
+        if misreported {
+            return PlayerCount {
+                players: server.players,
+                max_players: server.max_players,
+                queue,
+                source: CountSource::Api,
+                updated_at: now_secs(),
+                truncated,
+                misreported,
+            };
+        }
+
+        if truncated {
+            // A2S is still right about anything under the cap
+            let players = match a2s_players >= A2S_COUNT_CAP {
+                true => server.players.max(a2s_players),
+                false => a2s_players,
+            };
+
+            return PlayerCount {
+                players,
+                max_players: server.max_players.max(a2s_max_players),
+                queue,
+                source: CountSource::Mixed,
+                updated_at: now_secs(),
+                truncated,
+                misreported,
+            };
+        }
+
+        PlayerCount {
+            players: a2s_players,
+            max_players: a2s_max_players,
+            queue,
+            source: CountSource::A2S,
+            updated_at: now_secs(),
+            truncated,
+            misreported,
+        }
+    }
+}
+
+/// Reconciles an A2S_INFO response into the server's player count.
+pub fn apply_info(server: &mut Server, info: &Info) {
+    let count = PlayerCount::reconcile(server, info);
+    server.players = count.players;
+    server.max_players = count.max_players;
+    server.player_count = Some(count);
+}
+
+impl From<PlayerCount32> for PlayerCount {
+    fn from(count: PlayerCount32) -> Self {
+        PlayerCount {
+            players: count.players as i64,
+            max_players: count.max_players as i64,
+            queue: count.queue.map(|queue| queue as i64),
+            source: count.source,
+            updated_at: count.updated_at as i64,
+            truncated: count.truncated,
+            misreported: count.misreported,
+        }
+    }
+}
+
+impl From<PlayerCount> for PlayerCount32 {
+    fn from(count: PlayerCount) -> Self {
+        PlayerCount32 {
+            players: count.players as i32,
+            max_players: count.max_players as i32,
+            queue: count.queue.map(|queue| queue as i32),
+            source: count.source,
+            updated_at: count.updated_at as u32,
+            truncated: count.truncated,
+            misreported: count.misreported,
+        }
+    }
+}
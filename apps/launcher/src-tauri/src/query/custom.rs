@@ -0,0 +1,276 @@
+use super::now_secs;
+use super::reconcile;
+use super::rules;
+use super::scheduler::QueryPriority;
+use super::scheduler::QUERY_SCHEDULER;
+use super::Server;
+use super::Server32;
+use super::SERVER_MAP;
+use a2s::A2SClient;
+use anyhow::anyhow;
+use anyhow::Result;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// DayZ's default Steam query port, used when the user doesn't give us one.
+const DEFAULT_QUERY_PORT: u16 = 27016;
+
+/// A server the user added by hand, e.g. a private or whitelisted server
+/// that never shows up in the FTL master map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomServer {
+    /// What the user typed in, we keep it so we can show it back to them.
+    pub host: String,
+    /// Unix timestamp (seconds) of when the server was added.
+    pub added_at: i64,
+    /// Last known state, used until the next refresh queries it again.
+    pub server: Server,
+}
+
+/// Adds a server by `host:queryport`. The host can be a domain name, we resolve it
+/// and make sure something is actually answering A2S before we save it.
+/// Returns the new server entry, filled in from A2S info, EDF and rules.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_add_custom_server(
+    host: String,
+    app_handle: AppHandle,
+) -> Result<Server32, String> {
+    let addr = resolve(&host).await.map_err(|e| e.to_string())?;
+    println!("query_add_custom_server: {} resolved to {}", host, addr);
+
+    let server = build_server(addr)
+        .await
+        .map_err(|e| format!("Couldn't reach {} ({}): {}", host, addr, e))?;
+
+    let mut custom_servers = load(&app_handle).map_err(|e| e.to_string())?;
+    custom_servers.insert(
+        server.addr.clone(),
+        CustomServer {
+            host,
+            added_at: now_secs(),
+            server: server.clone(),
+        },
+    );
+    save(&app_handle, &custom_servers).map_err(|e| e.to_string())?;
+
+    // Put it in the map right away so it gets refreshed like any other server
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    server_map.insert(server.addr.clone(), server.clone());
+    drop(server_map);
+
+    Ok(server.into())
+}
+
+/// Removes a custom server. Does nothing if the server was never added.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_remove_custom_server(addr: String, app_handle: AppHandle) -> Result<(), String> {
+    let mut custom_servers = load(&app_handle).map_err(|e| e.to_string())?;
+    if custom_servers.remove(&addr).is_none() {
+        println!(
+            "query_remove_custom_server: {} is not a custom server!",
+            addr
+        );
+        return Ok(());
+    }
+    save(&app_handle, &custom_servers).map_err(|e| e.to_string())?;
+
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    if server_map.get(&addr).is_some_and(|server| server.custom) {
+        server_map.remove(&addr);
+    }
+
+    Ok(())
+}
+
+/// Lists every custom server, with the latest info we have for them.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_list_custom_servers(app_handle: AppHandle) -> Result<Vec<Server32>, String> {
+    let custom_servers = load(&app_handle).map_err(|e| e.to_string())?;
+    let server_map = SERVER_MAP.clone().lock_owned().await;
+
+    Ok(custom_servers
+        .into_values()
+        .map(|custom| match server_map.get(&custom.server.addr) {
+            Some(server) => server.clone().into(),
+            None => custom.server.into(),
+        })
+        .collect())
+}
+
+/// Merges the custom servers into a server map before it gets queried.
+/// Custom servers that were removed get dropped, new ones get their last known state.
+pub fn merge_into(app_handle: &AppHandle, server_map: &mut HashMap<String, Server>) -> Result<()> {
+    let custom_servers = load(app_handle)?;
+    merge(custom_servers, server_map);
+    Ok(())
+}
+
+/// Custom servers that are on the master list too keep the master entry, but still
+/// count as custom so they can be shown and removed as such.
+fn merge(custom_servers: HashMap<String, CustomServer>, server_map: &mut HashMap<String, Server>) {
+    server_map.retain(|addr, server| !server.custom || custom_servers.contains_key(addr));
+    for (addr, custom) in custom_servers {
+        server_map
+            .entry(addr)
+            .and_modify(|server| server.custom = true)
+            .or_insert(custom.server);
+    }
+}
+
+/// Saves the latest state of every custom server, so it's there even if the server map cache gets busted.
+pub fn snapshot(app_handle: &AppHandle, server_map: &HashMap<String, Server>) -> Result<()> {
+    let mut custom_servers = load(app_handle)?;
+    if custom_servers.is_empty() {
+        return Ok(());
+    }
+
+    for (addr, custom) in custom_servers.iter_mut() {
+        if let Some(server) = server_map.get(addr) {
+            custom.server = server.clone();
+        }
+    }
+
+    save(app_handle, &custom_servers)
+}
+
+/// Resolves `host[:port]` to an IPv4 socket address, A2S only speaks IPv4 here.
+async fn resolve(host: &str) -> Result<SocketAddr> {
+    let host = host.trim();
+    let with_port = match host.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+        _ => format!("{}:{}", host, DEFAULT_QUERY_PORT),
+    };
+
+    let addr = tokio::net::lookup_host(&with_port)
+        .await?
+        .find(|addr| addr.is_ipv4())
+        .ok_or(anyhow!("Could not resolve {} to an IPv4 address", host))?;
+    Ok(addr)
+}
+
+/// Queries a server and builds a full `Server` entry for it from info, EDF and rules.
+/// Errors if the server doesn't answer A2S_INFO, rules are optional.
+async fn build_server(addr: SocketAddr) -> Result<Server> {
+    let a2s_client = A2SClient::new().await?;
+    let key = addr.to_string();
+
+    let info = QUERY_SCHEDULER.run(&key, QueryPriority::Visible, async {
+        let start = Instant::now();
+        let info = a2s_client.info(addr).await?;
+        Ok::<_, a2s::errors::Error>((info, start.elapsed()))
+    });
+    let raw_rules = QUERY_SCHEDULER.run(&key, QueryPriority::Visible, a2s_client.rules_raw(addr));
+    let (info, raw_rules) = tokio::join!(info, raw_rules);
+
+    let (info, duration) = info?;
+    let mut server = Server::from_info(key, &info);
+    reconcile::apply_info(&mut server, &info);
+    server.ping = Some(duration.as_millis() as i64);
+    server.health.record_success(now_secs());
+    server.custom = true;
+
+    match raw_rules {
+        Ok(raw_rules) => {
+            let decoded = rules::decode(&raw_rules);
+            server.mod_list = decoded.mod_list();
+        }
+        Err(e) => println!("build_server: Could not get rules for {}: {}", addr, e),
+    }
+
+    Ok(server)
+}
+
+fn custom_servers_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(app_handle
+        .path()
+        .app_cache_dir()?
+        .join("custom_servers.json"))
+}
+
+/// Loads the custom servers, keyed by query address. Missing file means no custom servers.
+pub fn load(app_handle: &AppHandle) -> Result<HashMap<String, CustomServer>> {
+    let path = custom_servers_path(app_handle)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn save(app_handle: &AppHandle, custom_servers: &HashMap<String, CustomServer>) -> Result<()> {
+    let path = custom_servers_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, serde_json::to_string(custom_servers)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(addr: &str, name: &str, custom: bool) -> Server {
+        Server {
+            addr: addr.to_string(),
+            name: name.to_string(),
+            custom,
+            ..Default::default()
+        }
+    }
+
+    fn custom_server(addr: &str, name: &str) -> (String, CustomServer) {
+        let custom = CustomServer {
+            host: addr.to_string(),
+            added_at: 0,
+            server: server(addr, name, true),
+        };
+        (addr.to_string(), custom)
+    }
+
+    #[test]
+    fn marks_master_list_servers_as_custom() {
+        let addr = "1.2.3.4:27016";
+        let mut server_map = HashMap::from([(addr.to_string(), server(addr, "Master", false))]);
+
+        merge(
+            HashMap::from([custom_server(addr, "Saved")]),
+            &mut server_map,
+        );
+
+        // The master entry is newer, it stays but is custom now
+        let merged = &server_map[addr];
+        assert!(merged.custom);
+        assert_eq!(merged.name, "Master");
+    }
+
+    #[test]
+    fn adds_new_and_drops_removed_custom_servers() {
+        let removed = "5.6.7.8:27016";
+        let added = "9.9.9.9:27016";
+        let mut server_map =
+            HashMap::from([(removed.to_string(), server(removed, "Removed", true))]);
+
+        merge(
+            HashMap::from([custom_server(added, "Added")]),
+            &mut server_map,
+        );
+
+        assert!(!server_map.contains_key(removed));
+        assert_eq!(server_map[added].name, "Added");
+        assert!(server_map[added].custom);
+    }
+}
@@ -0,0 +1,154 @@
+use super::integrity;
+use super::keywords::DayzKeywords;
+use super::rules;
+use super::rules::DayzRules;
+use super::scheduler::QueryPriority;
+use super::scheduler::QUERY_SCHEDULER;
+use a2s::info::Info;
+use a2s::info::ServerOS;
+use a2s::info::ServerType;
+use a2s::A2SClient;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::time::Instant;
+
+/// Everything we can find out about a server over A2S.
+/// Each part is queried on its own, so one failing doesn't throw away the others.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct ServerDetails {
+    pub addr: String,
+    pub info: Option<InfoDetails>,
+    pub info_error: Option<String>,
+    pub rules: Option<DayzRules>,
+    pub rules_error: Option<String>,
+    pub players: Option<Vec<PlayerDetails>>,
+    pub players_error: Option<String>,
+}
+
+/// A2S_INFO, with the EDF fields and keywords decoded.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct InfoDetails {
+    pub name: String,
+    pub map: String,
+    pub folder: String,
+    pub game: String,
+    pub version: String,
+    pub protocol: u32,
+    pub app_id: u32,
+    pub players: u32,
+    pub max_players: u32,
+    pub bots: u32,
+    pub server_type: String,
+    pub os: String,
+    pub password_required: bool,
+    pub vac: bool,
+    /// EDF game port, this is the port you actually connect to.
+    pub game_port: Option<u32>,
+    /// EDF steam id, as a string since JS can't handle u64.
+    pub steam_id: Option<String>,
+    pub game_id: Option<String>,
+    pub keywords_raw: Option<String>,
+    pub keywords: Option<DayzKeywords>,
+    pub ping: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct PlayerDetails {
+    pub name: String,
+    pub score: i32,
+    /// Seconds connected.
+    pub duration: f32,
+}
+
+/// Queries A2S info, rules and players at the same time and returns whatever came back.
+/// `@param: addr` - The server's query address, `ip:query_port`.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_server_details(addr: String) -> Result<ServerDetails, String> {
+    let a2s_client = A2SClient::new().await.map_err(|e| e.to_string())?;
+
+    let info = QUERY_SCHEDULER.run(&addr, QueryPriority::Visible, async {
+        let start = Instant::now();
+        let info = a2s_client.info(&addr).await?;
+        Ok::<_, a2s::errors::Error>((info, start.elapsed()))
+    });
+    let rules = QUERY_SCHEDULER.run(&addr, QueryPriority::Visible, a2s_client.rules_raw(&addr));
+    let players = QUERY_SCHEDULER.run(&addr, QueryPriority::Visible, a2s_client.players(&addr));
+
+    let (info, rules, players) = tokio::join!(info, rules, players);
+
+    // Servers faking their count can't fake the player list as easily
+    if let (Ok((info, _)), Ok(players)) = (&info, &players) {
+        integrity::observe_players(&addr, info.players as i64, players.len() as i64).await;
+    }
+
+    let mut details = ServerDetails {
+        addr: addr.clone(),
+        ..Default::default()
+    };
+
+    match info {
+        Ok((info, duration)) => details.info = Some(info_details(info, duration.as_millis())),
+        Err(e) => details.info_error = Some(e.to_string()),
+    }
+
+    match rules {
+        Ok(raw_rules) => details.rules = Some(rules::decode(&raw_rules)),
+        Err(e) => details.rules_error = Some(e.to_string()),
+    }
+
+    match players {
+        Ok(players) => {
+            details.players = Some(
+                players
+                    .into_iter()
+                    .map(|player| PlayerDetails {
+                        name: player.name,
+                        score: player.score,
+                        duration: player.duration,
+                    })
+                    .collect(),
+            )
+        }
+        Err(e) => details.players_error = Some(e.to_string()),
+    }
+
+    Ok(details)
+}
+
+fn info_details(info: Info, ping: u128) -> InfoDetails {
+    let extended = info.extended_server_info;
+
+    InfoDetails {
+        name: info.name,
+        map: info.map,
+        folder: info.folder,
+        game: info.game,
+        version: info.version,
+        protocol: info.protocol as u32,
+        app_id: info.app_id as u32,
+        players: info.players as u32,
+        max_players: info.max_players as u32,
+        bots: info.bots as u32,
+        server_type: match info.server_type {
+            ServerType::Dedicated => "Dedicated",
+            ServerType::NonDedicated => "NonDedicated",
+            ServerType::SourceTV => "SourceTV",
+        }
+        .to_string(),
+        os: match info.server_os {
+            ServerOS::Linux => "Linux",
+            ServerOS::Windows => "Windows",
+            ServerOS::Mac => "Mac",
+        }
+        .to_string(),
+        password_required: info.visibility,
+        vac: info.vac,
+        game_port: extended.port.map(|port| port as u32),
+        steam_id: extended.steam_id.map(|id| id.to_string()),
+        game_id: extended.game_id.map(|id| id.to_string()),
+        keywords: extended.keywords.as_deref().map(DayzKeywords::parse),
+        keywords_raw: extended.keywords,
+        ping: ping as u32,
+    }
+}
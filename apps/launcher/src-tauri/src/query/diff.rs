@@ -0,0 +1,172 @@
+//! Diffs the server map between refreshes, so we can tell what changed:
+//! new and dead servers, version bumps, map rotations and mod updates.
+
+use super::now_secs;
+use super::Server;
+use anyhow::Result;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// How many refreshes worth of diffs we keep around.
+const MAX_DIFFS: usize = 10;
+
+/// One thing that changed about one server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind")]
+pub enum ServerChange {
+    Added {
+        addr: String,
+        name: String,
+    },
+    Removed {
+        addr: String,
+        name: String,
+    },
+    VersionChanged {
+        addr: String,
+        name: String,
+        from: String,
+        to: String,
+    },
+    MapChanged {
+        addr: String,
+        name: String,
+        from: String,
+        to: String,
+    },
+    /// Workshop ids, as strings since JS can't handle i64.
+    ModsChanged {
+        addr: String,
+        name: String,
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+/// Everything that changed in one refresh. Emitted once the refresh is done.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type, tauri_specta::Event)]
+pub struct ServerListDiffEvent {
+    pub refreshed_at: u32,
+    pub changes: Vec<ServerChange>,
+}
+
+/// Returns the diffs from the last few refreshes, oldest first.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_get_recent_diffs(
+    app_handle: AppHandle,
+) -> Result<Vec<ServerListDiffEvent>, String> {
+    load(&app_handle).map_err(|e| e.to_string())
+}
+
+/// Computes what changed between two server maps.
+/// LAN servers come and go with discovery, so they're left out.
+pub fn compute(
+    previous: &HashMap<String, Server>,
+    current: &HashMap<String, Server>,
+) -> ServerListDiffEvent {
+    let mut changes = Vec::new();
+
+    for (addr, server) in current.iter().filter(|(_, server)| !server.lan) {
+        let Some(old) = previous.get(addr).filter(|old| !old.lan) else {
+            changes.push(ServerChange::Added {
+                addr: addr.clone(),
+                name: server.name.clone(),
+            });
+            continue;
+        };
+
+        // Empty means we never heard, not that it changed
+        if !old.version.is_empty() && !server.version.is_empty() && old.version != server.version {
+            changes.push(ServerChange::VersionChanged {
+                addr: addr.clone(),
+                name: server.name.clone(),
+                from: old.version.clone(),
+                to: server.version.clone(),
+            });
+        }
+
+        if !old.map.is_empty() && !server.map.is_empty() && old.map != server.map {
+            changes.push(ServerChange::MapChanged {
+                addr: addr.clone(),
+                name: server.name.clone(),
+                from: old.map.clone(),
+                to: server.map.clone(),
+            });
+        }
+
+        let old_mods = mod_ids(old);
+        let new_mods = mod_ids(server);
+        if old_mods != new_mods {
+            let mut added: Vec<String> =
+                new_mods.difference(&old_mods).map(i64::to_string).collect();
+            let mut removed: Vec<String> =
+                old_mods.difference(&new_mods).map(i64::to_string).collect();
+            added.sort();
+            removed.sort();
+
+            changes.push(ServerChange::ModsChanged {
+                addr: addr.clone(),
+                name: server.name.clone(),
+                added,
+                removed,
+            });
+        }
+    }
+
+    for (addr, server) in previous.iter().filter(|(_, server)| !server.lan) {
+        let still_listed = current.get(addr).is_some_and(|server| !server.lan);
+        if !still_listed {
+            changes.push(ServerChange::Removed {
+                addr: addr.clone(),
+                name: server.name.clone(),
+            });
+        }
+    }
+
+    ServerListDiffEvent {
+        refreshed_at: now_secs() as u32,
+        changes,
+    }
+}
+
+/// Appends a diff to the history, dropping the oldest past `MAX_DIFFS`.
+pub fn record(app_handle: &AppHandle, diff: &ServerListDiffEvent) -> Result<()> {
+    let mut diffs = load(app_handle)?;
+    diffs.push(diff.clone());
+    if diffs.len() > MAX_DIFFS {
+        diffs.drain(..diffs.len() - MAX_DIFFS);
+    }
+
+    fs::write(diffs_path(app_handle)?, serde_json::to_string(&diffs)?)?;
+    Ok(())
+}
+
+fn load(app_handle: &AppHandle) -> Result<Vec<ServerListDiffEvent>> {
+    let path = diffs_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn diffs_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(app_handle.path().app_cache_dir()?.join("server_diffs.json"))
+}
+
+fn mod_ids(server: &Server) -> HashSet<i64> {
+    server
+        .mod_list
+        .iter()
+        .flatten()
+        .map(|dayz_mod| dayz_mod.workshop_id)
+        .collect()
+}
@@ -0,0 +1,278 @@
+//! The webview's way out to the internet, we proxy requests here to avoid CORS issues.
+//! Only allowlisted hosts over HTTPS, with timeouts and a size cap, and every response is
+//! cached on disk so we can revalidate with ETag/Last-Modified and still answer offline.
+
+use super::now_secs;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use reqwest::header;
+use reqwest::redirect;
+use reqwest::StatusCode;
+use reqwest::Url;
+use ring::digest;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// The only hosts the webview is allowed to reach through us.
+const ALLOWED_HOSTS: [&str; 2] = ["api.steampowered.com", "api.ftl-launcher.com"];
+
+/// Responses bigger than this are refused, nothing we fetch comes close.
+const MAX_BODY_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Cached responses younger than this are served without hitting the network.
+const FRESH_SECS: i64 = 30;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_REDIRECTS: usize = 5;
+
+lazy_static! {
+    static ref FETCH_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        // Redirects have to stay on the allowlist too
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS || !is_allowed(attempt.url()) {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .expect("Failed to build fetch client");
+}
+
+/// Why a fetch failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", content = "message")]
+pub enum FetchError {
+    InvalidUri(String),
+    /// The host isn't on the allowlist, or the scheme isn't HTTPS.
+    NotAllowed(String),
+    Timeout,
+    /// We couldn't reach the host and had nothing cached.
+    Offline(String),
+    /// The host answered with an error status and we had nothing cached.
+    Status(u16),
+    /// The response was bigger than we're willing to read, in bytes.
+    TooLarge(u32),
+    Body(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::InvalidUri(e) => write!(f, "Invalid URI: {}", e),
+            FetchError::NotAllowed(uri) => write!(f, "Not allowed to fetch: {}", uri),
+            FetchError::Timeout => write!(f, "Request timed out"),
+            FetchError::Offline(e) => write!(f, "Couldn't reach host: {}", e),
+            FetchError::Status(status) => write!(f, "Host responded with {}", status),
+            FetchError::TooLarge(size) => write!(f, "Response too large ({} bytes)", size),
+            FetchError::Body(e) => write!(f, "Failed to read response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.is_timeout() {
+            true => FetchError::Timeout,
+            false => FetchError::Offline(e.to_string()),
+        }
+    }
+}
+
+/// A cached response, one file per URI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    uri: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Unix timestamp (seconds) of when we last heard from the host about this URI.
+    fetched_at: i64,
+    body: String,
+}
+
+/// Fetches a URI for the webview and returns the body.
+/// Serves the cached copy when the host says nothing changed, or when we can't reach it.
+#[tauri::command]
+#[specta::specta]
+pub async fn fetch(uri: String, app_handle: AppHandle) -> Result<String, FetchError> {
+    let url = Url::parse(&uri).map_err(|e| FetchError::InvalidUri(e.to_string()))?;
+    if !is_allowed(&url) {
+        return Err(FetchError::NotAllowed(uri));
+    }
+
+    let cache_path = cache_path(&app_handle, &uri).ok();
+    let cached = cache_path.as_deref().and_then(load);
+
+    if let Some(cached) = cached.as_ref() {
+        if now_secs() - cached.fetched_at < FRESH_SECS {
+            return Ok(cached.body.clone());
+        }
+    }
+
+    match revalidate(url, cached.as_ref()).await {
+        Ok(entry) => {
+            if let Some(path) = cache_path.as_deref() {
+                if let Err(e) = save(path, &entry) {
+                    println!("fetch: Failed to cache {}: {}", uri, e);
+                }
+            }
+
+            Ok(entry.body)
+        }
+        // Something stale beats nothing, as long as the host was the problem and not us
+        Err(e @ (FetchError::Timeout | FetchError::Offline(_) | FetchError::Status(500..)))
+            if cached.is_some() =>
+        {
+            println!("fetch: {}, serving stale copy of {}", e, uri);
+            Ok(cached.unwrap().body)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Makes the request, conditional if we have a cached copy, and returns the new cache entry.
+async fn revalidate(url: Url, cached: Option<&CacheEntry>) -> Result<CacheEntry, FetchError> {
+    let uri = url.to_string();
+    let mut request = FETCH_CLIENT.get(url);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let mut response = request.send().await?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+            let mut entry = cached.clone();
+            entry.fetched_at = now_secs();
+            return Ok(entry);
+        }
+    }
+
+    if !response.status().is_success() {
+        return Err(FetchError::Status(response.status().as_u16()));
+    }
+
+    if let Some(length) = response.content_length() {
+        if length > MAX_BODY_BYTES {
+            return Err(FetchError::TooLarge(length.min(u32::MAX as u64) as u32));
+        }
+    }
+
+    let header_value = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let etag = header_value(header::ETAG);
+    let last_modified = header_value(header::LAST_MODIFIED);
+
+    // Content-Length can lie or be missing, so keep counting while we read
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > MAX_BODY_BYTES {
+            return Err(FetchError::TooLarge(body.len() as u32));
+        }
+    }
+
+    Ok(CacheEntry {
+        uri,
+        etag,
+        last_modified,
+        fetched_at: now_secs(),
+        body: String::from_utf8(body).map_err(|e| FetchError::Body(e.to_string()))?,
+    })
+}
+
+/// HTTPS to an allowed host on the default port, with no credentials in the URL.
+/// `Url` drops the port when it's the scheme's default, so an explicit `:443` is fine.
+fn is_allowed(url: &Url) -> bool {
+    url.scheme() == "https"
+        && url.port().is_none()
+        && url.username().is_empty()
+        && url.password().is_none()
+        && url
+            .host_str()
+            .is_some_and(|host| ALLOWED_HOSTS.contains(&host))
+}
+
+fn load(path: &Path) -> Option<CacheEntry> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn save(path: &Path, entry: &CacheEntry) -> Result<()> {
+    fs::write(path, serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Cache files are named after a hash of the URI, so any URI makes a safe file name.
+fn cache_path(app_handle: &AppHandle, uri: &str) -> Result<PathBuf> {
+    let dir = app_handle.path().app_cache_dir()?.join("fetch_cache");
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let hash = digest::digest(&digest::SHA256, uri.as_bytes());
+    let name: String = hash
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(dir.join(name + ".json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(uri: &str) -> bool {
+        is_allowed(&Url::parse(uri).unwrap())
+    }
+
+    #[test]
+    fn allows_https_to_allowed_hosts() {
+        assert!(allowed("https://api.ftl-launcher.com/v1/dayz/servers"));
+        assert!(allowed("https://api.steampowered.com:443/"));
+    }
+
+    #[test]
+    fn refuses_other_hosts_and_schemes() {
+        assert!(!allowed("http://api.ftl-launcher.com/"));
+        assert!(!allowed("https://example.com/"));
+        assert!(!allowed("https://api.ftl-launcher.com.example.com/"));
+    }
+
+    #[test]
+    fn refuses_non_default_ports() {
+        assert!(!allowed("https://api.ftl-launcher.com:8443/"));
+        assert!(!allowed("https://api.steampowered.com:80/"));
+    }
+
+    #[test]
+    fn refuses_credentials() {
+        assert!(!allowed("https://user:pw@api.ftl-launcher.com/"));
+        assert!(!allowed("https://user@api.ftl-launcher.com/"));
+        assert!(!allowed("https://:pw@api.ftl-launcher.com/"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Offline GeoIP. `Server.region` is a coarse Valve region code, so we look server IPs up
+//! in a local MaxMind format database (GeoLite2-City or similar) instead. The database is
+//! downloaded separately, we just read whatever file the settings point at.
+
+use super::settings;
+use super::settings::Coordinates;
+use super::settings::QuerySettings;
+use super::Server;
+use super::Server32;
+use super::SERVER_MAP;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use maxminddb::geoip2;
+use maxminddb::Reader;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tokio::sync::RwLock;
+
+/// Mean earth radius, close enough for "how far away is this server".
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+lazy_static! {
+    static ref GEOIP_DATABASE: Arc<RwLock<Option<GeoIpDatabase>>> = Arc::new(RwLock::new(None));
+}
+
+/// The open database, and the path it was opened from so we notice when it changes.
+struct GeoIpDatabase {
+    path: PathBuf,
+    reader: Reader<Vec<u8>>,
+}
+
+/// Where a server is, as far as the GeoIP database knows.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerLocation {
+    /// ISO 3166-1 alpha-2, e.g. `DE`.
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Great circle distance from the user's configured location.
+    pub distance_km: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum LocationSort {
+    /// Closest first, servers we couldn't place go last.
+    Distance,
+    /// By country code, then closest first.
+    Country,
+}
+
+/// Filters and sorting on server location.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct LocationFilter {
+    /// Only servers in these countries (ISO codes), everything if empty.
+    pub countries: Vec<String>,
+    /// Only servers at most this far away.
+    pub max_distance_km: Option<f64>,
+    pub sort: Option<LocationSort>,
+}
+
+/// Returns servers from the server map that match the location filter, sorted as asked.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_filter_by_location(
+    filter: LocationFilter,
+    app_handle: AppHandle,
+) -> Result<Vec<Server32>, String> {
+    let settings = settings::load(&app_handle).map_err(|e| e.to_string())?;
+
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    locate_all(&mut server_map, &settings).await;
+    let mut servers: Vec<Server> = server_map
+        .values()
+        .filter(|server| matches(server, &filter))
+        .cloned()
+        .collect();
+    drop(server_map);
+
+    if let Some(sort) = filter.sort {
+        servers.sort_by(|a, b| compare(a, b, sort));
+    }
+
+    Ok(servers.into_iter().map(|server| server.into()).collect())
+}
+
+/// Attaches a location to every server in the map. Does nothing if there's no database
+/// configured, a server that can't be found just gets no location.
+pub async fn locate_all(server_map: &mut HashMap<String, Server>, settings: &QuerySettings) {
+    let Some(db_path) = settings.geoip_db_path.as_ref().map(PathBuf::from) else {
+        return;
+    };
+
+    if let Err(e) = open(&db_path).await {
+        println!("geoip: Failed to open {}: {}", db_path.display(), e);
+        return;
+    }
+
+    let database = GEOIP_DATABASE.read().await;
+    let Some(database) = database.as_ref() else {
+        return;
+    };
+
+    // A lot of servers share a box, only look each IP up once
+    let mut lookups: HashMap<IpAddr, Option<ServerLocation>> = HashMap::new();
+    for server in server_map.values_mut() {
+        let ip = server
+            .addr
+            .rsplit_once(':')
+            .and_then(|(ip, _)| ip.parse::<IpAddr>().ok());
+        let Some(ip) = ip else {
+            continue;
+        };
+
+        let location = lookups
+            .entry(ip)
+            .or_insert_with(|| lookup(&database.reader, ip, settings.user_location.as_ref()));
+        server.location = location.clone();
+    }
+}
+
+/// Opens the database, unless it's already open from the same path.
+async fn open(db_path: &Path) -> Result<()> {
+    if let Some(database) = GEOIP_DATABASE.read().await.as_ref() {
+        if database.path == db_path {
+            return Ok(());
+        }
+    }
+
+    let reader = Reader::open_readfile(db_path)?;
+    *GEOIP_DATABASE.write().await = Some(GeoIpDatabase {
+        path: db_path.to_path_buf(),
+        reader,
+    });
+    Ok(())
+}
+
+fn lookup(
+    reader: &Reader<Vec<u8>>,
+    ip: IpAddr,
+    user_location: Option<&Coordinates>,
+) -> Option<ServerLocation> {
+    let city: geoip2::City = reader.lookup(ip).ok()?;
+    let english = |names: Option<&BTreeMap<&str, &str>>| {
+        names
+            .and_then(|names| names.get("en"))
+            .map(|name| name.to_string())
+    };
+
+    let country = city.country.as_ref();
+    let coordinates = city.location.as_ref();
+    let latitude = coordinates.and_then(|location| location.latitude);
+    let longitude = coordinates.and_then(|location| location.longitude);
+
+    let distance_km = match (latitude, longitude, user_location) {
+        (Some(latitude), Some(longitude), Some(user)) => Some(haversine_km(
+            user,
+            &Coordinates {
+                latitude,
+                longitude,
+            },
+        )),
+        _ => None,
+    };
+
+    Some(ServerLocation {
+        country_code: country
+            .and_then(|country| country.iso_code)
+            .map(|code| code.to_string()),
+        country: english(country.and_then(|country| country.names.as_ref())),
+        city: english(city.city.as_ref().and_then(|city| city.names.as_ref())),
+        latitude,
+        longitude,
+        distance_km,
+    })
+}
+
+/// Great circle distance between two points.
+pub fn haversine_km(a: &Coordinates, b: &Coordinates) -> f64 {
+    let (lat_a, lat_b) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let d_lat = (b.latitude - a.latitude).to_radians();
+    let d_lon = (b.longitude - a.longitude).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+fn matches(server: &Server, filter: &LocationFilter) -> bool {
+    let location = server.location.as_ref();
+
+    if !filter.countries.is_empty() {
+        let country_code = location.and_then(|location| location.country_code.as_ref());
+        let in_countries = country_code.is_some_and(|code| {
+            filter
+                .countries
+                .iter()
+                .any(|country| country.eq_ignore_ascii_case(code))
+        });
+        if !in_countries {
+            return false;
+        }
+    }
+
+    if let Some(max_distance_km) = filter.max_distance_km {
+        let distance_km = location.and_then(|location| location.distance_km);
+        if !distance_km.is_some_and(|distance_km| distance_km <= max_distance_km) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn compare(a: &Server, b: &Server, sort: LocationSort) -> Ordering {
+    let distance = |server: &Server| {
+        server
+            .location
+            .as_ref()
+            .and_then(|location| location.distance_km)
+            .unwrap_or(f64::INFINITY)
+    };
+    let by_distance = distance(a).total_cmp(&distance(b));
+
+    match sort {
+        LocationSort::Distance => by_distance,
+        LocationSort::Country => {
+            let country = |server: &Server| {
+                server
+                    .location
+                    .as_ref()
+                    .and_then(|location| location.country_code.clone())
+            };
+
+            // Unknown countries go last
+            match (country(a), country(b)) {
+                (Some(a), Some(b)) => a.cmp(&b).then(by_distance),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => by_distance,
+            }
+        }
+    }
+}
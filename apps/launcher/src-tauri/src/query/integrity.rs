@@ -0,0 +1,201 @@
+//! Duplicate and fake server detection.
+//! Some listings are the same box under several query ports, and some servers lie about
+//! their player counts to climb the list. We group the duplicates and tag anything that
+//! looks off, the settings decide whether the user gets to see them.
+
+use super::keywords;
+use super::Server;
+use super::SERVER_MAP;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+
+/// A count that hasn't moved for this many queries...
+const FROZEN_MIN_SAMPLES: u32 = 6;
+
+/// ...over at least this long (seconds), is almost certainly made up.
+const FROZEN_MIN_SECS: i64 = 6 * 60 * 60;
+
+/// Below this many players, a small A2S_PLAYER list isn't worth flagging.
+const PHANTOM_MIN_PLAYERS: i64 = 10;
+
+/// Flag the server if A2S_PLAYER lists less than this fraction of the reported count.
+const PHANTOM_MIN_RATIO: f64 = 0.5;
+
+/// Why a server looks suspicious.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum SuspicionFlag {
+    /// Reports more players than it has slots.
+    PlayersOverMax,
+    /// Reports the exact same non zero player count for hours.
+    FrozenCount,
+    /// Reports far more players than it lists in A2S_PLAYER.
+    PhantomPlayers,
+}
+
+/// What we've seen of a server's player counts over time. Backend only.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountHistory {
+    pub players: i64,
+    /// Unix timestamp (seconds) of when `players` was first seen.
+    pub unchanged_since: i64,
+    /// How many queries in a row reported `players`.
+    pub samples: u32,
+    /// Reported count and A2S_PLAYER list length from the last player query.
+    pub listed: Option<(i64, i64)>,
+}
+
+/// Records a fresh player count, call this after every successful A2S_INFO.
+pub fn observe_count(server: &mut Server, now: i64) {
+    let history = &mut server.count_history;
+    if history.samples > 0 && history.players == server.players {
+        history.samples = history.samples.saturating_add(1);
+    } else {
+        history.players = server.players;
+        history.unchanged_since = now;
+        history.samples = 1;
+    }
+}
+
+/// Records how many players a server reported vs. how many it actually listed.
+pub async fn observe_players(addr: &str, reported: i64, listed: i64) {
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    if let Some(server) = server_map.get_mut(addr) {
+        server.count_history.listed = Some((reported, listed));
+    }
+}
+
+/// Groups duplicates and tags suspicious servers across the whole map.
+/// Every server's `duplicate_of` and `flags` are recomputed from scratch.
+pub fn analyze(server_map: &mut HashMap<String, Server>, now: i64) {
+    for server in server_map.values_mut() {
+        server.flags = flags(server, now);
+        server.duplicate_of = None;
+    }
+
+    // Same steam id, or same ip + game port, is the same server
+    let addrs: Vec<String> = server_map.keys().cloned().collect();
+    let mut groups = UnionFind::new(addrs.len());
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (i, addr) in addrs.iter().enumerate() {
+        let server = &server_map[addr];
+        let ip = addr.rsplit_once(':').map(|(ip, _)| ip).unwrap_or(addr);
+
+        let mut keys = vec![format!("{}:{}", ip, server.game_port)];
+        if !server.steam_id.is_empty() && server.steam_id != "0" {
+            keys.push(server.steam_id.clone());
+        }
+
+        for key in keys {
+            match seen.get(&key) {
+                Some(j) => groups.union(i, *j),
+                None => {
+                    seen.insert(key, i);
+                }
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<&String>> = HashMap::new();
+    for (i, addr) in addrs.iter().enumerate() {
+        members.entry(groups.find(i)).or_default().push(addr);
+    }
+
+    for group in members.values().filter(|group| group.len() > 1) {
+        let primary = group
+            .iter()
+            .min_by_key(|addr| rank(&server_map[**addr]))
+            .map(|addr| (*addr).clone())
+            .unwrap();
+
+        for addr in group.iter().filter(|addr| ***addr != primary) {
+            if let Some(server) = server_map.get_mut(*addr) {
+                server.duplicate_of = Some(primary.clone());
+            }
+        }
+    }
+}
+
+fn flags(server: &Server, now: i64) -> Vec<SuspicionFlag> {
+    let mut flags = Vec::new();
+
+    let misreported = server
+        .player_count
+        .as_ref()
+        .is_some_and(|count| count.misreported);
+    if misreported || server.players > server.max_players {
+        flags.push(SuspicionFlag::PlayersOverMax);
+    }
+
+    // Full or queued servers sit at their cap for hours, that's just popular
+    let full = server.max_players > 0 && server.players >= server.max_players;
+    let queued = keywords::login_queue(&server.game_type).is_some_and(|queue| queue > 0);
+
+    let history = &server.count_history;
+    if !full
+        && !queued
+        && history.players > 0
+        && history.samples >= FROZEN_MIN_SAMPLES
+        && now - history.unchanged_since >= FROZEN_MIN_SECS
+    {
+        flags.push(SuspicionFlag::FrozenCount);
+    }
+
+    if let Some((reported, listed)) = history.listed {
+        if reported >= PHANTOM_MIN_PLAYERS && (listed as f64) < reported as f64 * PHANTOM_MIN_RATIO
+        {
+            flags.push(SuspicionFlag::PhantomPlayers);
+        }
+    }
+
+    flags
+}
+
+/// Which server in a duplicate group gets to stay, lowest wins.
+/// Reachable beats unreachable, then the lowest ping, then the address so it's stable.
+fn rank(server: &Server) -> (bool, i64, &str) {
+    (
+        server.ping.is_none(),
+        server.ping.unwrap_or(i64::MAX),
+        server.addr.as_str(),
+    )
+}
+
+/// Bare bones union find, plenty for grouping a few thousand servers.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Path compression, so the next lookup is a single hop
+        let mut i = i;
+        while self.parents[i] != root {
+            let next = self.parents[i];
+            self.parents[i] = root;
+            i = next;
+        }
+
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a] = b;
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Helpers for the DayZ A2S keywords (gametype) string.
+//! DayZ packs a bunch of server state in here as a comma separated list,
+//! e.g. `battleye,no3rd,external,privHive,shard,lqs0,etm3.000000,entm6.000000,mod,12:34`.
+
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// Decoded DayZ keywords.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct DayzKeywords {
+    pub battleye: bool,
+    /// Servers only tell us when third person is *disabled* (`no3rd`).
+    pub third_person: bool,
+    pub external: bool,
+    pub private_hive: bool,
+    pub shard: Option<String>,
+    pub modded: bool,
+    /// Login queue size (`lqs<N>`).
+    pub login_queue: Option<i32>,
+    /// Day time acceleration (`etm<F>`).
+    pub time_acceleration: Option<f32>,
+    /// Night time acceleration (`entm<F>`).
+    pub night_time_acceleration: Option<f32>,
+    /// In game time of day, `HH:MM`.
+    pub time: Option<String>,
+    /// Anything we don't know how to decode yet.
+    pub unknown: Vec<String>,
+}
+
+impl DayzKeywords {
+    pub fn parse(keywords: &str) -> DayzKeywords {
+        let mut decoded = DayzKeywords {
+            third_person: true,
+            ..Default::default()
+        };
+
+        for tag in keywords.split(',').map(str::trim) {
+            if tag.is_empty() {
+                continue;
+            }
+
+            match tag {
+                "battleye" => decoded.battleye = true,
+                "no3rd" => decoded.third_person = false,
+                "external" => decoded.external = true,
+                "privHive" => decoded.private_hive = true,
+                "mod" => decoded.modded = true,
+                _ => {
+                    // NOTE: Order matters here, `entm` would also match `etm` otherwise
+                    if let Some(queue) = tag.strip_prefix("lqs") {
+                        decoded.login_queue = queue.parse().ok();
+                    } else if let Some(accel) = tag.strip_prefix("entm") {
+                        decoded.night_time_acceleration = accel.parse().ok();
+                    } else if let Some(accel) = tag.strip_prefix("etm") {
+                        decoded.time_acceleration = accel.parse().ok();
+                    } else if let Some(shard) = tag.strip_prefix("shard") {
+                        decoded.shard = Some(shard.to_string());
+                    } else if is_time_of_day(tag) {
+                        decoded.time = Some(tag.to_string());
+                    } else {
+                        decoded.unknown.push(tag.to_string());
+                    }
+                }
+            }
+        }
+
+        decoded
+    }
+}
+
+/// Returns the login queue size (`lqs<N>`) if the server reported one.
+pub fn login_queue(keywords: &str) -> Option<i64> {
+    keywords
+        .split(',')
+        .find_map(|tag| tag.trim().strip_prefix("lqs"))
+        .and_then(|queue| queue.parse().ok())
+}
+
+fn is_time_of_day(tag: &str) -> bool {
+    match tag.split_once(':') {
+        Some((hours, minutes)) => {
+            hours.parse::<u8>().is_ok_and(|h| h < 24) && minutes.parse::<u8>().is_ok_and(|m| m < 60)
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,212 @@
+//! LAN server discovery. DayZ servers on the local network usually never make it to the
+//! master server list, so we shout A2S_INFO at the broadcast address and see who answers.
+
+use super::now_secs;
+use super::reconcile;
+use super::Server;
+use super::Server32;
+use super::SERVER_MAP;
+use a2s::info::Info;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::net::SocketAddrV4;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+/// `0xFFFFFFFF` + `TSource Engine Query\0`
+const INFO_REQUEST: &[u8] = b"\xFF\xFF\xFF\xFFTSource Engine Query\0";
+
+/// Query ports DayZ servers usually sit on. 27016 is the default,
+/// extra instances on the same box tend to count up from there.
+const LAN_QUERY_PORTS: [u16; 9] = [27015, 27016, 27017, 27018, 27019, 27020, 2303, 2304, 2305];
+
+/// How long we listen for replies by default.
+const DEFAULT_WINDOW_MS: u32 = 1500;
+
+/// Broadcasts A2S_INFO on the local subnets and returns every DayZ server that answered.
+/// Discovered servers are marked as LAN and added to the server map, so they can be
+/// refreshed and joined like any other server.
+/// `@param: window_ms` - How long to collect replies for, defaults to 1.5 seconds.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_discover_lan(window_ms: Option<u32>) -> Result<Vec<Server32>, String> {
+    let window = Duration::from_millis(window_ms.unwrap_or(DEFAULT_WINDOW_MS) as u64);
+
+    let mut targets = Vec::new();
+    for broadcast in broadcast_addrs().await {
+        for port in LAN_QUERY_PORTS {
+            targets.push(SocketAddr::V4(SocketAddrV4::new(broadcast, port)));
+        }
+    }
+
+    let servers = discover(&targets, window)
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("query_discover_lan: Found {} LAN server(s)", servers.len());
+
+    // Swap out whatever we found last time, LAN servers come and go
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    server_map.retain(|_, server| !server.lan);
+    for server in servers.iter() {
+        server_map
+            .entry(server.addr.clone())
+            .or_insert(server.clone());
+    }
+    drop(server_map);
+
+    Ok(servers.into_iter().map(|server| server.into()).collect())
+}
+
+/// Sends A2S_INFO to every target and collects replies until `window` runs out.
+/// Targets can be broadcast or unicast addresses, which makes this easy to point
+/// at a local responder.
+pub async fn discover(targets: &[SocketAddr], window: Duration) -> Result<Vec<Server>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.set_broadcast(true)?;
+
+    let sent_at = Instant::now();
+    for target in targets {
+        // One unreachable subnet shouldn't stop the rest
+        if let Err(e) = socket.send_to(INFO_REQUEST, target).await {
+            println!("discover: Failed to send to {}: {}", target, e);
+        }
+    }
+
+    let deadline = sent_at + window;
+    let mut found: HashMap<SocketAddr, Server> = HashMap::new();
+    let mut buf = vec![0u8; 1400];
+
+    loop {
+        let received = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await;
+        let (len, from) = match received {
+            Ok(Ok(received)) => received,
+            Ok(Err(e)) => {
+                // Windows surfaces ICMP port unreachable here, just keep listening
+                println!("discover: Error receiving: {}", e);
+                continue;
+            }
+            Err(_) => break,
+        };
+
+        // Everything we care about starts with the single packet header
+        if len < 5 || buf[..4] != [0xFF, 0xFF, 0xFF, 0xFF] || found.contains_key(&from) {
+            continue;
+        }
+
+        match buf[4] {
+            // S2C_CHALLENGE, newer servers want the challenge back before they answer
+            b'A' if len >= 9 => {
+                let mut query = INFO_REQUEST.to_vec();
+                query.extend_from_slice(&buf[5..9]);
+                let _ = socket.send_to(&query, from).await;
+            }
+            b'I' => match Info::from_cursor(Cursor::new(buf[4..len].to_vec())) {
+                Ok(info) => {
+                    let mut server = Server::from_info(from.to_string(), &info);
+                    reconcile::apply_info(&mut server, &info);
+                    server.ping = Some(sent_at.elapsed().as_millis() as i64);
+                    server.health.record_success(now_secs());
+                    server.lan = true;
+                    found.insert(from, server);
+                }
+                Err(e) => println!("discover: Bad A2S_INFO from {}: {}", from, e),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(found.into_values().collect())
+}
+
+/// Broadcast addresses for the subnets we're on. We don't have a portable way to list
+/// interfaces, so we use the limited broadcast plus the /24 of our outbound address,
+/// which covers pretty much every home and office LAN.
+async fn broadcast_addrs() -> Vec<Ipv4Addr> {
+    let mut addrs = vec![Ipv4Addr::BROADCAST];
+
+    // Connecting a UDP socket doesn't send anything, it just picks the outbound interface
+    let local_ip = async {
+        let socket = UdpSocket::bind("0.0.0.0:0").await.ok()?;
+        socket.connect("8.8.8.8:80").await.ok()?;
+        match socket.local_addr().ok()? {
+            SocketAddr::V4(addr) => Some(*addr.ip()),
+            SocketAddr::V6(_) => None,
+        }
+    }
+    .await;
+
+    if let Some(ip) = local_ip {
+        let [a, b, c, _] = ip.octets();
+        addrs.push(Ipv4Addr::new(a, b, c, 255));
+    }
+
+    addrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DayZ A2S_INFO reply: dedicated Windows server, password set, port, steam id,
+    /// keywords and game id in the extra data.
+    fn info_reply() -> Vec<u8> {
+        let mut reply = vec![0xFF, 0xFF, 0xFF, 0xFF, b'I', 17];
+        for field in ["LAN Test Server", "chernarusplus", "dayz", "DayZ"] {
+            reply.extend_from_slice(field.as_bytes());
+            reply.push(0);
+        }
+        reply.extend_from_slice(&221100u32.to_le_bytes()[..2]);
+        reply.extend_from_slice(&[12, 60, 0, b'd', b'w', 1, 1]);
+        reply.extend_from_slice(b"1.26.159040\0");
+        reply.push(0x80 | 0x10 | 0x20 | 0x01);
+        reply.extend_from_slice(&2302u16.to_le_bytes());
+        reply.extend_from_slice(&90000000000000001u64.to_le_bytes());
+        reply.extend_from_slice(b"battleye,no3rd,lqs0,etm4.000000,entm1.000000\0");
+        reply.extend_from_slice(&221100u64.to_le_bytes());
+        reply
+    }
+
+    #[tokio::test]
+    async fn discovers_local_responder() {
+        let responder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let responder_addr = responder.local_addr().unwrap();
+
+        // Asks for a challenge first, like current servers do, then answers the retry
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1400];
+            let (len, from) = responder.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], INFO_REQUEST);
+            responder
+                .send_to(&[0xFF, 0xFF, 0xFF, 0xFF, b'A', 1, 2, 3, 4], from)
+                .await
+                .unwrap();
+
+            let (len, from) = responder.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..INFO_REQUEST.len()], INFO_REQUEST);
+            assert_eq!(&buf[INFO_REQUEST.len()..len], &[1, 2, 3, 4]);
+            responder.send_to(&info_reply(), from).await.unwrap();
+        });
+
+        let servers = discover(&[responder_addr], Duration::from_millis(500))
+            .await
+            .unwrap();
+
+        assert_eq!(servers.len(), 1);
+        let server = &servers[0];
+        assert_eq!(server.addr, responder_addr.to_string());
+        assert_eq!(server.name, "LAN Test Server");
+        assert_eq!(server.map, "chernarusplus");
+        assert_eq!(server.version, "1.26.159040");
+        assert_eq!(server.players, 12);
+        assert_eq!(server.max_players, 60);
+        assert_eq!(server.game_port, 2302);
+        assert_eq!(server.app_id, 221100);
+        assert!(server.password_required);
+        assert!(server.lan);
+        assert!(server.ping.is_some());
+    }
+}
@@ -0,0 +1,531 @@
+//! Syncing the FTL master server map. Most entries don't change between launches, so
+//! instead of pulling the whole map every time we ask for what changed since the revision
+//! we last saw, and only fall back to a full download when that doesn't work out.
+//!
+//! Full map: `GET /v1/dayz/servers`, conditional on ETag/Last-Modified.
+//! Delta: `GET /v1/dayz/servers/delta?since=<revision>`, returns a `MasterDelta`.
+//!
+//! The API allows one request a second, so a rate limited request waits and goes once more.
+
+use super::FTLAPIResponse;
+use super::Server;
+use anyhow::anyhow;
+use anyhow::Result;
+use reqwest::header;
+use reqwest::StatusCode;
+use ring::digest;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const SERVERS_ENDPOINT: &str = "/v1/dayz/servers";
+const DELTA_ENDPOINT: &str = "/v1/dayz/servers/delta";
+
+/// The full map is a few MB, give it some room.
+const MASTER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to back off after a 429 without a `Retry-After`, and the most we'll wait with one.
+const RATE_LIMIT_WAIT: Duration = Duration::from_secs(1);
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(10);
+
+/// Our copy of the master map, exactly as the API gave it to us, plus what we need
+/// to ask for changes next time. Persisted between launches.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterState {
+    pub revision: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub server_map: HashMap<String, Server>,
+}
+
+/// What changed since `from_revision`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterDelta {
+    pub from_revision: String,
+    pub revision: String,
+    #[serde(default)]
+    pub added: HashMap<String, Server>,
+    #[serde(default)]
+    pub changed: HashMap<String, Server>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    /// How many servers the map should have once the delta is applied.
+    pub total: usize,
+    /// SHA-256 (hex) of the resulting map, see `checksum_of`.
+    pub checksum: Option<String>,
+}
+
+/// How a sync went.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncOutcome {
+    /// Nothing changed since last time.
+    NotModified,
+    Delta {
+        added: usize,
+        changed: usize,
+        removed: usize,
+    },
+    Full,
+}
+
+impl MasterState {
+    /// Loads the state from disk. Anything missing or unreadable means a full download.
+    pub fn load(path: &Path) -> MasterState {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Brings `state` up to date with the master server at `base_uri`.
+/// Tries a delta first, and falls back to a (conditional) full download if there is
+/// no revision to start from, the delta request fails, or it doesn't check out.
+pub async fn sync(base_uri: &str, state: &mut MasterState) -> Result<SyncOutcome> {
+    let client = reqwest::Client::builder().timeout(MASTER_TIMEOUT).build()?;
+
+    if let Some(revision) = state.revision.clone() {
+        if !state.server_map.is_empty() {
+            match fetch_delta(&client, base_uri, &revision).await {
+                Ok(delta) => match apply_delta(state, delta) {
+                    Ok(outcome) => return Ok(outcome),
+                    Err(e) => println!("master_sync: Delta failed integrity check: {}", e),
+                },
+                Err(e) => println!("master_sync: Delta unavailable: {}", e),
+            }
+        }
+    }
+
+    fetch_full(&client, base_uri, state).await
+}
+
+async fn fetch_delta(
+    client: &reqwest::Client,
+    base_uri: &str,
+    revision: &str,
+) -> Result<MasterDelta> {
+    let request = client
+        .get(base_uri.to_owned() + DELTA_ENDPOINT)
+        .query(&[("since", revision)]);
+    let response = send(request).await?.error_for_status()?;
+
+    Ok(response.json::<MasterDelta>().await?)
+}
+
+/// Applies a delta to a copy of the map, and only keeps it if the result checks out.
+pub fn apply_delta(state: &mut MasterState, delta: MasterDelta) -> Result<SyncOutcome> {
+    if state.revision.as_deref() != Some(delta.from_revision.as_str()) {
+        return Err(anyhow!(
+            "Delta starts at {}, we're at {:?}",
+            delta.from_revision,
+            state.revision
+        ));
+    }
+
+    let unchanged = delta.added.is_empty() && delta.changed.is_empty() && delta.removed.is_empty();
+    if unchanged && delta.revision == delta.from_revision {
+        return Ok(SyncOutcome::NotModified);
+    }
+
+    let outcome = SyncOutcome::Delta {
+        added: delta.added.len(),
+        changed: delta.changed.len(),
+        removed: delta.removed.len(),
+    };
+
+    let mut server_map = state.server_map.clone();
+    for addr in delta.removed.iter() {
+        server_map.remove(addr);
+    }
+    server_map.extend(delta.added);
+    server_map.extend(delta.changed);
+
+    if server_map.len() != delta.total {
+        return Err(anyhow!(
+            "Expected {} servers, got {}",
+            delta.total,
+            server_map.len()
+        ));
+    }
+    if let Some(checksum) = delta.checksum.as_deref() {
+        let actual = checksum_of(&server_map);
+        if !actual.eq_ignore_ascii_case(checksum) {
+            return Err(anyhow!(
+                "Checksum mismatch, expected {}, got {}",
+                checksum,
+                actual
+            ));
+        }
+    }
+
+    state.server_map = server_map;
+    state.revision = Some(delta.revision);
+    Ok(outcome)
+}
+
+async fn fetch_full(
+    client: &reqwest::Client,
+    base_uri: &str,
+    state: &mut MasterState,
+) -> Result<SyncOutcome> {
+    let mut request = client.get(base_uri.to_owned() + SERVERS_ENDPOINT);
+
+    // Only conditional if we actually have something to fall back on
+    if !state.server_map.is_empty() {
+        if let Some(etag) = &state.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = send(request).await?;
+    if response.status() == StatusCode::NOT_MODIFIED && !state.server_map.is_empty() {
+        return Ok(SyncOutcome::NotModified);
+    }
+
+    let response = response.error_for_status()?;
+    let header_value = |name: header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let etag = header_value(header::ETAG);
+    let last_modified = header_value(header::LAST_MODIFIED);
+
+    let data = response.json::<FTLAPIResponse>().await?;
+    *state = MasterState {
+        revision: data.revision,
+        etag,
+        last_modified,
+        server_map: data.server_map,
+    };
+
+    Ok(SyncOutcome::Full)
+}
+
+/// Sends a request, and if it gets rate limited waits as long as the API asks and tries once more.
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let retry = request.try_clone();
+    let response = request.send().await?;
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok(response);
+    }
+    let Some(retry) = retry else {
+        return Ok(response);
+    };
+
+    let wait = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(RATE_LIMIT_WAIT)
+        .min(MAX_RATE_LIMIT_WAIT);
+    tokio::time::sleep(wait).await;
+
+    Ok(retry.send().await?)
+}
+
+/// SHA-256 (hex) of the map, one `canonical_line` per server sorted by address and `\n`
+/// joined. Has to match the sentinel's `checksum`.
+pub fn checksum_of(server_map: &HashMap<String, Server>) -> String {
+    let mut addrs: Vec<&String> = server_map.keys().collect();
+    addrs.sort_unstable();
+
+    let lines: Vec<String> = addrs
+        .into_iter()
+        .map(|addr| canonical_line(addr, &server_map[addr]))
+        .collect();
+    let hash = digest::digest(&digest::SHA256, lines.join("\n").as_bytes());
+    hash.as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The address followed by every field the API sends, in its order, tab separated.
+/// Mods are `workshopId:name`, comma separated.
+fn canonical_line(addr: &str, server: &Server) -> String {
+    let mods: Vec<String> = server
+        .mod_list
+        .iter()
+        .flatten()
+        .map(|m| format!("{}:{}", m.workshop_id, m.name))
+        .collect();
+
+    [
+        addr.to_string(),
+        server.addr.clone(),
+        server.game_port.to_string(),
+        server.steam_id.clone(),
+        server.name.clone(),
+        server.app_id.to_string(),
+        server.game_dir.clone(),
+        server.version.clone(),
+        server.product.clone(),
+        server.region.to_string(),
+        server.players.to_string(),
+        server.max_players.to_string(),
+        server.bots.to_string(),
+        server.map.clone(),
+        server.secure.to_string(),
+        server.dedicated.to_string(),
+        server.os.clone(),
+        server.game_type.clone(),
+        mods.join(","),
+    ]
+    .join("\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Mod;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// A stand-in for the FTL API. Answers each connection with the next canned response
+    /// and records the request lines it saw. Returns the base URI.
+    async fn stand_in(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_uri = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                seen.lock()
+                    .unwrap()
+                    .push(request.lines().next().unwrap_or_default().to_string());
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        (base_uri, requests)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+        for header in headers {
+            response += header;
+            response += "\r\n";
+        }
+        response + &format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    fn server_map(addrs: &[&str]) -> HashMap<String, Server> {
+        addrs
+            .iter()
+            .map(|addr| {
+                let server = Server {
+                    addr: addr.to_string(),
+                    ..Default::default()
+                };
+                (addr.to_string(), server)
+            })
+            .collect()
+    }
+
+    fn synced_state() -> MasterState {
+        MasterState {
+            revision: Some("r1".to_string()),
+            etag: Some("\"r1\"".to_string()),
+            last_modified: None,
+            server_map: server_map(&["1.1.1.1:2303", "2.2.2.2:2303"]),
+        }
+    }
+
+    #[tokio::test]
+    async fn applies_delta() {
+        let mut expected = synced_state().server_map;
+        expected.remove("2.2.2.2:2303");
+        expected.extend(server_map(&["3.3.3.3:2303"]));
+        let delta = MasterDelta {
+            from_revision: "r1".to_string(),
+            revision: "r2".to_string(),
+            added: server_map(&["3.3.3.3:2303"]),
+            changed: HashMap::new(),
+            removed: vec!["2.2.2.2:2303".to_string()],
+            total: 2,
+            checksum: Some(checksum_of(&expected)),
+        };
+        let body = serde_json::to_string(&delta).unwrap();
+        let (base_uri, requests) = stand_in(vec![response("200 OK", &[], &body)]).await;
+
+        let mut state = synced_state();
+        let outcome = sync(&base_uri, &mut state).await.unwrap();
+
+        assert_eq!(
+            outcome,
+            SyncOutcome::Delta {
+                added: 1,
+                changed: 0,
+                removed: 1
+            }
+        );
+        assert_eq!(state.server_map, expected);
+        assert_eq!(state.revision.as_deref(), Some("r2"));
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec!["GET /v1/dayz/servers/delta?since=r1 HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_full_download_through_rate_limit() {
+        let full = FTLAPIResponse {
+            server_map: server_map(&["4.4.4.4:2303"]),
+            revision: Some("r9".to_string()),
+        };
+        let body = serde_json::to_string(&full).unwrap();
+        let (base_uri, requests) = stand_in(vec![
+            response("404 Not Found", &[], "{}"),
+            response("429 Too Many Requests", &["Retry-After: 0"], "{}"),
+            response("200 OK", &["ETag: \"r9\""], &body),
+        ])
+        .await;
+
+        let mut state = synced_state();
+        let outcome = sync(&base_uri, &mut state).await.unwrap();
+
+        assert_eq!(outcome, SyncOutcome::Full);
+        assert_eq!(state.server_map, full.server_map);
+        assert_eq!(state.revision.as_deref(), Some("r9"));
+        assert_eq!(state.etag.as_deref(), Some("\"r9\""));
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "GET /v1/dayz/servers/delta?since=r1 HTTP/1.1",
+                "GET /v1/dayz/servers HTTP/1.1",
+                "GET /v1/dayz/servers HTTP/1.1",
+            ]
+        );
+    }
+
+    fn full_server() -> Server {
+        Server {
+            addr: "1.1.1.1:2303".to_string(),
+            game_port: 2302,
+            steam_id: "90000000000000001".to_string(),
+            name: "Test".to_string(),
+            app_id: 221100,
+            game_dir: "dayz".to_string(),
+            version: "1.26".to_string(),
+            product: "dayz".to_string(),
+            region: 255,
+            players: 10,
+            max_players: 60,
+            bots: 0,
+            map: "chernarusplus".to_string(),
+            secure: true,
+            dedicated: true,
+            os: "w".to_string(),
+            game_type: "battleye".to_string(),
+            mod_list: Some(vec![
+                Mod {
+                    workshop_id: 1559212036,
+                    name: "CF".to_string(),
+                },
+                Mod {
+                    workshop_id: 1564026768,
+                    name: "Community-Online-Tools".to_string(),
+                },
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn checksum_matches_the_sentinel() {
+        // Same map and hash as TestChecksumMatchesLauncher in the sentinel
+        let server_map = HashMap::from([("1.1.1.1:2303".to_string(), full_server())]);
+        assert_eq!(
+            checksum_of(&server_map),
+            "dacbb52336808304ee5d112069e7dcbe9dec5844ddbbe0e1af0d059a9d907458"
+        );
+    }
+
+    #[test]
+    fn rejects_delta_with_wrong_field_values() {
+        let mut state = synced_state();
+        let changed = Server {
+            players: 40,
+            ..full_server()
+        };
+        let mut expected = state.server_map.clone();
+        expected.insert(changed.addr.clone(), changed.clone());
+
+        // Right addresses, wrong player count
+        let delta = MasterDelta {
+            from_revision: "r1".to_string(),
+            revision: "r2".to_string(),
+            changed: HashMap::from([(
+                changed.addr.clone(),
+                Server {
+                    players: 41,
+                    ..changed
+                },
+            )]),
+            total: 2,
+            checksum: Some(checksum_of(&expected)),
+            ..Default::default()
+        };
+
+        let error = apply_delta(&mut state, delta).unwrap_err();
+        assert!(error.to_string().starts_with("Checksum mismatch"));
+        assert_eq!(state, synced_state());
+    }
+
+    #[tokio::test]
+    async fn rejects_delta_that_does_not_check_out() {
+        let delta = MasterDelta {
+            from_revision: "r1".to_string(),
+            revision: "r2".to_string(),
+            added: server_map(&["3.3.3.3:2303"]),
+            total: 2,
+            ..Default::default()
+        };
+        let body = serde_json::to_string(&delta).unwrap();
+        let (base_uri, _) = stand_in(vec![
+            response("200 OK", &[], &body),
+            response("304 Not Modified", &[], ""),
+        ])
+        .await;
+
+        let mut state = synced_state();
+        let outcome = sync(&base_uri, &mut state).await.unwrap();
+
+        assert_eq!(outcome, SyncOutcome::NotModified);
+        assert_eq!(state, synced_state());
+    }
+}
@@ -1,37 +1,63 @@
+use a2s::info::Info;
+use a2s::info::ServerOS;
 use a2s::A2SClient;
 use anyhow::Result;
 use directories::BaseDirs;
 use futures::stream::{self, StreamExt};
 use lazy_static::lazy_static;
-use reqwest;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use std::collections::HashMap;
 use std::fs;
 use std::sync::Arc;
-use std::time::Duration;
 use std::time::Instant;
 use std::time::SystemTime;
 use tauri::dev;
 use tauri::AppHandle;
 use tauri::Manager;
+use tauri_specta::Event;
 use tokio::sync::Mutex;
-use tokio::sync::RwLock;
 use tokio::sync::Semaphore;
 
+use counts::PlayerCount;
+use counts::PlayerCount32;
+use geo::ServerLocation;
+use integrity::CountHistory;
+use integrity::SuspicionFlag;
+use ping::PingStats;
+use ping::PingStats32;
+use reconcile::FieldSources;
+use reconcile::ServerField;
+use scheduler::QueryError;
+pub use scheduler::QueryPriority;
+use scheduler::QUERY_SCHEDULER;
+use status::ServerHealth;
+use status::ServerHealth32;
+use status::ServerStatus;
+
+pub mod alerts;
+pub mod counts;
+pub mod custom;
+pub mod details;
+pub mod diff;
+pub mod fetcher;
+pub mod geo;
+pub mod integrity;
+pub mod keywords;
+pub mod lan;
+pub mod master;
+pub mod ping;
+pub mod reconcile;
+pub mod rules;
+pub mod scheduler;
+pub mod settings;
+pub mod status;
+
 lazy_static! {
     /// We store the server_map here, this is a HashMap<String, Server>
     /// where the key is the server's QUERY IP ADDRESS.
     static ref SERVER_MAP: Arc<Mutex<HashMap<String, Server>>> =
         Arc::new(Mutex::new(HashMap::new()));
-
-    /// We store the max updates here, this is a RwLock<usize>
-    /// where the value is the max number of concurrent server queries.
-    static ref MAX_UPDATES: Arc<RwLock<usize>> = Arc::new(RwLock::new(10));
-
-    /// A semaphore to limit concurrent server queries from the frontend.
-    static ref MAX_UPDATES_SEMAPHORE: Arc<RwLock<Semaphore>> =
-        Arc::new(RwLock::new(Semaphore::new(10)));
 }
 
 /// This function is the only function that is exposed to the Tauri frontend.
@@ -48,6 +74,7 @@ pub async fn get_server_list(app_handle: AppHandle) -> Result<Vec<Server32>, Str
         .app_cache_dir()
         .or_else(|_| Err("ERROR: Could not find app cache dir!"))?
         .join("server_map.json");
+    let settings = settings::load(&app_handle).map_err(|e| e.to_string())?;
 
     if !server_map_path.exists() {
         println!("Server list does not exist, fetching server list...");
@@ -61,8 +88,17 @@ pub async fn get_server_list(app_handle: AppHandle) -> Result<Vec<Server32>, Str
             .map_err(|e| e.to_string())?;
     }
 
-    let server_map_locked = SERVER_MAP.clone().lock_owned().await;
-    let server_list: Vec<Server> = server_map_locked.values().cloned().collect();
+    // Tag duplicates and fakes, then leave out whatever the user doesn't want to see
+    let mut server_map_locked = SERVER_MAP.clone().lock_owned().await;
+    integrity::analyze(&mut server_map_locked, now_secs());
+    geo::locate_all(&mut server_map_locked, &settings).await;
+    let server_list: Vec<Server> = server_map_locked
+        .values()
+        .filter(|server| !settings.hide_duplicates || server.duplicate_of.is_none())
+        .filter(|server| !settings.hide_flagged || server.flags.is_empty())
+        .cloned()
+        .collect();
+    drop(server_map_locked);
     println!("get_server_list(): Returning server list...");
 
     // Convert Server to Server32
@@ -73,59 +109,27 @@ pub async fn get_server_list(app_handle: AppHandle) -> Result<Vec<Server32>, Str
     Ok(server_list)
 }
 
-/// This function is used to fetch data from a URI.
-/// We do this here to avoid CORS issues.
+/// Sets the max number of concurrent server queries from the frontend.
+/// The scheduler may run fewer than this while it is backing off from timeouts.
 #[tauri::command]
 #[specta::specta]
-pub async fn fetch(uri: String) -> Result<String, String> {
-    let response = reqwest::get(&uri).await;
-    match response {
-        Ok(response) => {
-            let json = response.text().await.unwrap();
-            return Ok(json);
-        }
-        Err(e) => {
-            return Err(e.to_string());
-        }
+pub async fn query_set_max_concurrency(max_updates: u32) -> Result<(), String> {
+    if max_updates == 0 {
+        return Err("Max concurrent queries must be at least 1!".to_string());
     }
-}
-
-/// This function is called to destroy the server info semaphore.
-/// We do this to clear the waiting list of permits, or to change the limit.
-/// NOTE: Eventually we will want to let users pick how many servers to query at once.
-#[tauri::command]
-#[specta::specta]
-pub async fn destroy_server_info_semaphore() -> Result<(), String> {
-    let semaphore = MAX_UPDATES_SEMAPHORE.clone();
-    let semaphore = semaphore.read().await;
-    semaphore.close();
-    drop(semaphore);
-
-    let new_semaphore = MAX_UPDATES_SEMAPHORE.clone();
-    let mut new_semaphore = new_semaphore.write().await;
-    let max_updates = MAX_UPDATES.clone().read().await.clone();
-    *new_semaphore = Semaphore::new(max_updates);
 
+    QUERY_SCHEDULER.set_max_concurrency(max_updates as usize);
     Ok(())
 }
 
-/// This function is called to destroy the server info semaphore.
-/// We do this to clear the waiting list of permits, or to change the limit.
-/// NOTE: Eventually we will want to let users pick how many servers to query at once.
+/// Cancels any queued or running queries for the given servers.
+/// The frontend calls this when rows scroll out of view.
 #[tauri::command]
 #[specta::specta]
-pub async fn update_server_info_semaphore(max_updates: i32) -> Result<(), String> {
-    let semaphore = MAX_UPDATES_SEMAPHORE.clone();
-    let semaphore = semaphore.read().await;
-    semaphore.close();
-    drop(semaphore);
-
-    let new_semaphore = MAX_UPDATES_SEMAPHORE.clone();
-    let mut new_semaphore = new_semaphore.write().await;
-    let mut _m = MAX_UPDATES.clone().write().await.clone();
-    _m = max_updates as usize;
-
-    *new_semaphore = Semaphore::new(max_updates.try_into().unwrap());
+pub async fn query_cancel(addrs: Vec<String>) -> Result<(), String> {
+    for addr in addrs {
+        QUERY_SCHEDULER.cancel(&addr);
+    }
 
     Ok(())
 }
@@ -133,86 +137,83 @@ pub async fn update_server_info_semaphore(max_updates: i32) -> Result<(), String
 /// This function is called to get server information.
 /// We query the server and return the server information.
 /// `@param: server` - The server to query.
-/// TODO : Add error handling to unwraps, what if we cant get a new client?
+/// `@param: priority` - Where this query sits in line, visible rows go first.
 #[tauri::command]
 #[specta::specta]
-pub async fn get_server_info(server: Server32) -> Result<Server32, String> {
-    let server: Server = server.into();
-    let semaphore = MAX_UPDATES_SEMAPHORE.clone();
-    let semaphore = semaphore.read().await;
-    let permit = semaphore.acquire().await;
-
-    if permit.is_err() {
-        println!("⚠️ Failed to acquire permit for: {}", server.name);
+pub async fn get_server_info(
+    server: Server32,
+    priority: QueryPriority,
+) -> Result<Server32, String> {
+    let mut server: Server = server.into();
+
+    // The frontend's copy of the server can be stale, the backend knows best
+    // about how the last few queries went. Failing servers wait out their backoff.
+    if let Some(cached) = SERVER_MAP.clone().lock_owned().await.get(&server.addr) {
+        server.health = cached.health.clone();
+        server.count_history = cached.count_history.clone();
+    }
+    if !server.health.should_query(now_secs()) {
         return Ok(server.into());
     }
 
-    // Don't spawn servers! So lets sleep for a second.
-    tokio::time::sleep(Duration::from_millis(1000)).await;
-
     let a2s_client = A2SClient::new().await.map_err(|e| e.to_string())?;
 
-    let start = SystemTime::now();
-    let response = a2s_client.info(server.addr.clone()).await;
-    let end = SystemTime::now();
-    let duration = end.duration_since(start).map_err(|e| e.to_string())?;
+    let addr = server.addr.clone();
+    let response = QUERY_SCHEDULER
+        .run(&server.addr, priority, async {
+            let start = Instant::now();
+            let info = a2s_client.info(addr).await?;
+            Ok::<_, a2s::errors::Error>((info, start.elapsed()))
+        })
+        .await;
 
     match response {
-        Ok(info) => {
+        Ok((info, duration)) => {
             println!("Server query was successful: {}", server.name);
-            return Ok(Server {
-                addr: server.addr,
-                name: server.name,
-                game_port: server.game_port,
-                players: info.players as i64,
-                max_players: info.max_players as i64,
-                ping: Some(duration.as_millis() as i64),
-                steam_id: server.steam_id,
-                app_id: server.app_id,
-                game_dir: server.game_dir,
-                version: server.version,
-                product: server.product,
-                region: server.region,
-                bots: server.bots,
-                map: server.map,
-                secure: server.secure,
-                dedicated: server.dedicated,
-                os: server.os,
-                game_type: server.game_type,
-                mod_list: server.mod_list,
-            }
-            .into());
+            reconcile::apply_info(&mut server, &info);
+            server.ping = Some(duration.as_millis() as i64);
+            server.health.record_success(now_secs());
         }
-        Err(e) => {
+        Err(QueryError::Cancelled) => {
+            return Err(format!("Query cancelled for: {}", server.name));
+        }
+        Err(QueryError::A2S(e)) => {
             println!("Error getting server info: {}", e);
-            return Ok(Server {
-                addr: server.addr,
-                name: server.name,
-                game_port: server.game_port,
-                players: 0,
-                max_players: server.max_players,
-                ping: Some(99999),
-                steam_id: server.steam_id,
-                app_id: server.app_id,
-                game_dir: server.game_dir,
-                version: server.version,
-                product: server.product,
-                region: server.region,
-                bots: server.bots,
-                map: server.map,
-                secure: server.secure,
-                dedicated: server.dedicated,
-                os: server.os,
-                game_type: server.game_type,
-                mod_list: server.mod_list,
-            }
-            .into());
+            server.ping = None;
+            server
+                .health
+                .record_failure(ServerStatus::from_error(&e), now_secs());
         }
     }
+
+    // Keep the backend's map in sync so backoff survives the frontend's copy
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    if let Some(cached) = server_map.get_mut(&server.addr) {
+        cached.game_port = server.game_port;
+        cached.steam_id = server.steam_id.clone();
+        cached.app_id = server.app_id;
+        cached.version = server.version.clone();
+        cached.secure = server.secure;
+        cached.password_required = server.password_required;
+        cached.map = server.map.clone();
+        cached.game_type = server.game_type.clone();
+        cached.sources = server.sources.clone();
+        cached.mismatches = server.mismatches.clone();
+        cached.players = server.players;
+        cached.max_players = server.max_players;
+        cached.player_count = server.player_count.clone();
+        cached.ping = server.ping;
+        cached.health = server.health.clone();
+        cached.count_history = server.count_history.clone();
+    }
+    drop(server_map);
+
+    Ok(server.into())
 }
 
 /// This function is called to refresh the server cache.
-/// We skip all servers with marked as Some in the ping field, this means
+/// We skip servers that are still backing off from failed queries,
+/// everything else gets queried again.
 pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
     let server_map_path = app_handle.path().app_cache_dir()?.join("server_map.json");
 
@@ -220,9 +221,10 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
     let server_map_json_local = fs::read_to_string(server_map_path.clone())?;
     let mut server_map_local: HashMap<String, Server> =
         serde_json::from_str(&server_map_json_local)?;
+    let server_map_previous = server_map_local.clone();
 
     // Grab remote server_map
-    fetch_master_server_map().await?;
+    fetch_master_server_map(&app_handle).await?;
     let server_map_remote = SERVER_MAP.clone().lock_owned().await.clone();
 
     // Merge remote map into local map, overwriting any existing keys
@@ -242,15 +244,25 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
             new_server.version = value.version.clone();
             new_server.players = value.players.clone();
             new_server.max_players = value.max_players.clone();
+            new_server.player_count = value.player_count.clone();
             new_server.bots = value.bots.clone();
             new_server.map = value.map.clone();
             new_server.secure = value.secure.clone();
             new_server.os = value.os.clone();
             new_server.game_type = value.game_type.clone();
             new_server.mod_list = value.mod_list.clone();
+
+            // Fresh API values, A2S gets to check them again. Old mismatches stay
+            // flagged until a query confirms or clears them.
+            new_server.sources = value.sources.clone();
         }
     }
 
+    // User added servers aren't in the master map, make sure they're in there too
+    // LAN servers get rediscovered on demand, they don't survive a relaunch
+    custom::merge_into(&app_handle, &mut server_map_local)?;
+    server_map_local.retain(|_, server| !server.lan);
+
     // Update the global SERVER_MAP with the new local map
     // Then create a stream from the SERVER_MAP
     let mut server_map = SERVER_MAP.clone().lock_owned().await;
@@ -269,7 +281,7 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
             let semaphore_cloned = semaphore.clone();
 
             async move {
-                if server.1.ping.is_some() {
+                if !server.1.health.should_query(now_secs()) {
                     println!("Skipping server: {}", server.1.name);
                     return;
                 }
@@ -286,23 +298,17 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
                     match response {
                         Ok(info) => {
                             println!("Updating server: {}", server.name);
-                            // NOTE: @see https://github.com/danlikestocode/ftl-launcher/issues/1
-                            // server.players = info.players as i64;
-                            // server.max_players = info.max_players as i64;
-                            server.map = info.map;
+                            reconcile::apply_info(server, &info);
                             server.ping = Some(duration.as_millis() as i64);
-
-                            // For some reason the author of Rust A2S
-                            // decided to rename gametype to keywords ?????
-                            if let Some(keywords) = info.extended_server_info.keywords {
-                                server.game_type = keywords;
-                            }
+                            server.health.record_success(now_secs());
                         }
                         Err(e) => {
                             println!("Error querying server: {}", server.name);
                             println!("Error: {}", e);
-                            server.players = 0;
-                            server.ping = Some(99999);
+                            server.ping = None;
+                            server
+                                .health
+                                .record_failure(ServerStatus::from_error(&e), now_secs());
                         }
                     }
                 }
@@ -313,9 +319,19 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
     // Collect JSON
     let server_map_locked = SERVER_MAP.clone().lock_owned().await;
     let server_map_json = serde_json::to_string(&*server_map_locked)?;
+    custom::snapshot(&app_handle, &server_map_locked)?;
+    let server_diff = diff::compute(&server_map_previous, &server_map_locked);
     drop(server_map_locked);
     println!("refresh_server_cache(): Finished querying!");
 
+    // Let the frontend know what changed since last time
+    println!(
+        "refresh_server_cache(): {} change(s) since last refresh",
+        server_diff.changes.len()
+    );
+    diff::record(&app_handle, &server_diff)?;
+    server_diff.emit(&app_handle)?;
+
     // Delete and write server_map to cache
     fs::remove_file(server_map_path.clone())?;
     fs::write(server_map_path, server_map_json)?;
@@ -328,7 +344,12 @@ pub async fn refresh_server_cache(app_handle: AppHandle) -> Result<()> {
 /// This function will trigger anytime the FTLL local cache is deleted.
 /// TODO: Add error handling to unwraps
 pub async fn init_server_cache(app_handle: AppHandle) -> Result<()> {
-    fetch_master_server_map().await?;
+    fetch_master_server_map(&app_handle).await?;
+
+    // User added servers aren't in the master map, make sure they're in there too
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    custom::merge_into(&app_handle, &mut server_map)?;
+    drop(server_map);
 
     // Create A2SClient, Semaphore, and Stream
     // Semaphore is to prevent port exhaustion
@@ -355,20 +376,16 @@ pub async fn init_server_cache(app_handle: AppHandle) -> Result<()> {
                     match response {
                         Ok(info) => {
                             println!("Updating server: {}", server.name);
-                            // NOTE: @see https://github.com/danlikestocode/ftl-launcher/issues/1
-                            // server.players = info.players as i64;
-                            // server.max_players = info.max_players as i64;
+                            reconcile::apply_info(server, &info);
                             server.ping = Some(duration.as_millis() as i64);
-                            server.map = info.map;
-
-                            if let Some(keywords) = info.extended_server_info.keywords {
-                                server.game_type = keywords;
-                            }
+                            server.health.record_success(now_secs());
                         }
-                        Err(_) => {
+                        Err(e) => {
                             println!("Error querying server: {}", server.name);
-                            server.players = 0;
-                            server.ping = Some(99999);
+                            server.ping = None;
+                            server
+                                .health
+                                .record_failure(ServerStatus::from_error(&e), now_secs());
                         }
                     }
                 }
@@ -379,6 +396,7 @@ pub async fn init_server_cache(app_handle: AppHandle) -> Result<()> {
     // Collect JSON
     let server_map_locked = SERVER_MAP.clone().lock_owned().await;
     let server_map_json = serde_json::to_string(&*server_map_locked)?;
+    custom::snapshot(&app_handle, &server_map_locked)?;
     drop(server_map_locked);
     println!("init_server_cache(): Finished querying!");
 
@@ -398,8 +416,9 @@ pub async fn init_server_cache(app_handle: AppHandle) -> Result<()> {
 
 /// This function is called to fetch the server_map from the FTL API.
 /// The server_map is a HashMap<String, Server> where the key is the server's steamid.
-/// Set to a static atomic reference, thread safe.
-async fn fetch_master_server_map() -> Result<()> {
+/// We keep the last master map on disk and only pull what changed since then,
+/// @see master::sync. Set to a static atomic reference, thread safe.
+async fn fetch_master_server_map(app_handle: &AppHandle) -> Result<()> {
     // Check if we are in dev mode
     let is_dev = dev();
     println!("is_dev: {}", is_dev);
@@ -411,40 +430,27 @@ async fn fetch_master_server_map() -> Result<()> {
     let dev_uri = "http://api.ftl-launcher.com";
     // let dev_uri = "http://localhost:8080";
     let prod_uri = "https://api.ftl-launcher.com";
-    let endpoint = "/v1/dayz/servers";
-
-    match is_dev {
-        true => {
-            println!(
-                "Fetching server map from dev... {}",
-                dev_uri.to_owned() + endpoint
-            );
-
-            let data = reqwest::get(dev_uri.to_owned() + endpoint)
-                .await?
-                .json::<FTLAPIResponse>()
-                .await?;
-
-            let mut server_map = SERVER_MAP.clone().lock_owned().await;
-            *server_map = data.server_map;
-            Ok(())
-        }
-        false => {
-            println!(
-                "Fetching server map from prod... {}",
-                prod_uri.to_owned() + endpoint
-            );
-
-            let data = reqwest::get(prod_uri.to_owned() + endpoint)
-                .await?
-                .json::<FTLAPIResponse>()
-                .await?;
-
-            let mut server_map = SERVER_MAP.clone().lock_owned().await;
-            *server_map = data.server_map;
-            Ok(())
-        }
+    let base_uri = match is_dev {
+        true => dev_uri,
+        false => prod_uri,
+    };
+
+    let master_path = app_handle.path().app_cache_dir()?.join("master_map.json");
+    let mut master = master::MasterState::load(&master_path);
+
+    println!("Syncing server map from... {}", base_uri);
+    let outcome = master::sync(base_uri, &mut master).await?;
+    println!("Server map sync: {:?}", outcome);
+    if outcome != master::SyncOutcome::NotModified {
+        master.save(&master_path)?;
     }
+
+    let mut server_map_master = master.server_map;
+    stamp_player_counts(&mut server_map_master);
+
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    *server_map = server_map_master;
+    Ok(())
 }
 
 /// This function is called on the first launch of the application.
@@ -477,11 +483,29 @@ pub fn init_appdata() -> Result<()> {
     Ok(())
 }
 
+/// Returns the current unix timestamp in seconds.
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
 /// Data Structure FTLAPIResponse
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FTLAPIResponse {
     pub server_map: HashMap<String, Server>,
+    /// Revision of this map, the starting point for the next delta sync.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+}
+
+/// Marks every server's player count as coming from the API snapshot.
+fn stamp_player_counts(server_map: &mut HashMap<String, Server>) {
+    for server in server_map.values_mut() {
+        server.player_count = Some(PlayerCount::from_api(server));
+    }
 }
 
 /// Server Data Structure
@@ -509,6 +533,84 @@ pub struct Server {
     pub mod_list: Option<Vec<Mod>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ping: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player_count: Option<PlayerCount>,
+    #[serde(default)]
+    pub health: ServerHealth,
+    /// Added by the user, not from the FTL master map.
+    #[serde(default)]
+    pub custom: bool,
+    /// Found by LAN discovery, not from the FTL master map.
+    #[serde(default)]
+    pub lan: bool,
+    /// A2S visibility flag, the server wants a password to join.
+    #[serde(default)]
+    pub password_required: bool,
+    /// Where each of the fields A2S can correct came from.
+    #[serde(default)]
+    pub sources: FieldSources,
+    /// Fields where the FTL API disagreed with what the server reported.
+    #[serde(default)]
+    pub mismatches: Vec<ServerField>,
+    /// Query address of the listing this one is a duplicate of.
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// Why this server looks suspicious, empty if it doesn't.
+    #[serde(default)]
+    pub flags: Vec<SuspicionFlag>,
+    #[serde(default)]
+    pub count_history: CountHistory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<ServerLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_stats: Option<PingStats>,
+}
+
+impl Server {
+    /// Builds a server entry from nothing but an A2S_INFO response.
+    /// Used for servers that aren't in the FTL master map.
+    pub fn from_info(addr: String, info: &Info) -> Server {
+        let extended = &info.extended_server_info;
+        let query_port = addr
+            .rsplit_once(':')
+            .and_then(|(_, port)| port.parse::<i64>().ok())
+            .unwrap_or_default();
+
+        Server {
+            game_port: extended.port.map(|port| port as i64).unwrap_or(query_port),
+            steam_id: extended
+                .steam_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            name: info.name.clone(),
+            // The 16 bit app id is truncated for DayZ, the real one is in the low 24 bits
+            app_id: extended
+                .game_id
+                .map(|id| (id & 0xFFFFFF) as i64)
+                .unwrap_or(info.app_id as i64),
+            game_dir: info.folder.clone(),
+            version: info.version.clone(),
+            product: info.folder.clone(),
+            region: 255,
+            players: info.players as i64,
+            max_players: info.max_players as i64,
+            bots: info.bots as i64,
+            map: info.map.clone(),
+            secure: info.vac,
+            dedicated: matches!(info.server_type, a2s::info::ServerType::Dedicated),
+            os: match info.server_os {
+                ServerOS::Linux => "l",
+                ServerOS::Windows => "w",
+                ServerOS::Mac => "m",
+            }
+            .to_string(),
+            game_type: extended.keywords.clone().unwrap_or_default(),
+            password_required: info.visibility,
+            sources: FieldSources::a2s(),
+            addr,
+            ..Default::default()
+        }
+    }
 }
 
 /// Mod Data Structure
@@ -543,6 +645,28 @@ pub struct Server32 {
     pub mod_list: Option<Vec<Mod32>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ping: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player_count: Option<PlayerCount32>,
+    #[serde(default)]
+    pub health: ServerHealth32,
+    #[serde(default)]
+    pub custom: bool,
+    #[serde(default)]
+    pub lan: bool,
+    #[serde(default)]
+    pub password_required: bool,
+    #[serde(default)]
+    pub sources: FieldSources,
+    #[serde(default)]
+    pub mismatches: Vec<ServerField>,
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    #[serde(default)]
+    pub flags: Vec<SuspicionFlag>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<ServerLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_stats: Option<PingStats32>,
 }
 
 /// 32 Bit Mod Data Structure (JS can't handle i64)
@@ -581,6 +705,19 @@ impl From<Server32> for Server {
                     .collect()
             }),
             ping: server.ping.map(|ping| ping as i64),
+            player_count: server.player_count.map(|count| count.into()),
+            health: server.health.into(),
+            custom: server.custom,
+            lan: server.lan,
+            password_required: server.password_required,
+            sources: server.sources,
+            mismatches: server.mismatches,
+            duplicate_of: server.duplicate_of,
+            flags: server.flags,
+            // Never leaves the backend, get_server_info restores it from SERVER_MAP
+            count_history: CountHistory::default(),
+            location: server.location,
+            ping_stats: server.ping_stats.map(|stats| stats.into()),
         }
     }
 }
@@ -623,6 +760,17 @@ impl Into<Server32> for Server {
                     .collect()
             }),
             ping: self.ping.map(|ping| ping as i32),
+            player_count: self.player_count.map(|count| count.into()),
+            health: self.health.into(),
+            custom: self.custom,
+            lan: self.lan,
+            password_required: self.password_required,
+            sources: self.sources,
+            mismatches: self.mismatches,
+            duplicate_of: self.duplicate_of,
+            flags: self.flags,
+            location: self.location,
+            ping_stats: self.ping_stats.map(|stats| stats.into()),
         }
     }
 }
@@ -0,0 +1,257 @@
+//! Multi-sample pings. One A2S round trip is noisy, and one dropped packet makes a server
+//! look dead, so we send a handful of spaced probes and look at the whole spread.
+
+use super::now_secs;
+use super::scheduler::QueryError;
+use super::scheduler::QueryPriority;
+use super::scheduler::QUERY_SCHEDULER;
+use super::settings;
+use super::SERVER_MAP;
+use a2s::A2SClient;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tauri::AppHandle;
+use tokio::sync::RwLock;
+use tokio::task;
+use tokio::time;
+
+/// Probes per measurement, unless the caller asks for something else.
+const DEFAULT_SAMPLES: u32 = 5;
+const MAX_SAMPLES: u32 = 20;
+
+/// Gap between probes, so one burst of congestion doesn't hit every sample.
+const PROBE_SPACING: Duration = Duration::from_millis(250);
+
+/// How often background mode re-measures favorites.
+const BACKGROUND_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+lazy_static! {
+    static ref IS_PING_WATCHER_RUNNING: Arc<RwLock<bool>> = Arc::new(RwLock::new(false));
+    /// Query addresses the background watcher measures, favorites live in the frontend.
+    static ref PING_FAVORITES: Arc<RwLock<Vec<String>>> = Arc::new(RwLock::new(Vec::new()));
+}
+
+/// Result of a multi-sample ping.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingStats {
+    pub samples: u32,
+    pub received: u32,
+    pub min_ms: u32,
+    pub median_ms: u32,
+    pub p95_ms: u32,
+    /// Mean difference between consecutive round trips.
+    pub jitter_ms: u32,
+    pub loss_percent: f32,
+    /// 0 - 100, higher is better. Combines latency, jitter and loss for sorting.
+    pub quality: u32,
+    /// Unix timestamp (seconds) of the measurement.
+    pub measured_at: i64,
+}
+
+/// 32 Bit Ping Stats Data Structure (JS can't handle i64)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct PingStats32 {
+    pub samples: u32,
+    pub received: u32,
+    pub min_ms: u32,
+    pub median_ms: u32,
+    pub p95_ms: u32,
+    pub jitter_ms: u32,
+    pub loss_percent: f32,
+    pub quality: u32,
+    pub measured_at: u32,
+}
+
+/// Measures a server with `samples` spaced probes and stores the result on the server.
+/// `@param: addr` - The server's query address.
+/// `@param: samples` - How many probes to send, defaults to 5, at most 20.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_measure_ping(addr: String, samples: Option<u32>) -> Result<PingStats32, String> {
+    let samples = samples.unwrap_or(DEFAULT_SAMPLES).clamp(1, MAX_SAMPLES);
+    let stats = measure(&addr, samples, QueryPriority::Visible)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    store(&addr, &stats).await;
+    Ok(stats.into())
+}
+
+/// Sets which servers background mode measures, usually the user's favorites.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_set_ping_favorites(addrs: Vec<String>) -> Result<(), String> {
+    *PING_FAVORITES.write().await = addrs;
+    Ok(())
+}
+
+/// Starts the background ping watcher, safe to call more than once.
+/// It only measures anything while `background_ping` is on in the query settings.
+#[tauri::command]
+#[specta::specta]
+pub async fn query_start_ping_watcher(app_handle: AppHandle) -> Result<(), String> {
+    let is_ping_watcher_running_ref = IS_PING_WATCHER_RUNNING.clone();
+    let mut is_ping_watcher_running = is_ping_watcher_running_ref.write().await;
+    if *is_ping_watcher_running {
+        return Ok(());
+    }
+
+    *is_ping_watcher_running = true;
+
+    task::spawn(async move {
+        let handle = app_handle.clone();
+
+        loop {
+            let enabled = settings::load(&handle)
+                .map(|settings| settings.background_ping)
+                .unwrap_or(false);
+
+            if enabled {
+                let favorites = PING_FAVORITES.read().await.clone();
+                for addr in favorites {
+                    match measure(&addr, DEFAULT_SAMPLES, QueryPriority::Favorite).await {
+                        Ok(stats) => store(&addr, &stats).await,
+                        Err(e) => println!("ping_watcher: {}: {}", addr, e),
+                    }
+                }
+            }
+
+            time::sleep(BACKGROUND_INTERVAL).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Sends `samples` A2S_INFO probes, `PROBE_SPACING` apart, and summarizes the round trips.
+/// Each probe goes through the scheduler on its own, so we never hog a slot while waiting.
+pub async fn measure(addr: &str, samples: u32, priority: QueryPriority) -> Result<PingStats> {
+    let a2s_client = A2SClient::new().await?;
+    let mut rtts: Vec<u32> = Vec::with_capacity(samples as usize);
+
+    for i in 0..samples {
+        if i > 0 {
+            time::sleep(PROBE_SPACING).await;
+        }
+
+        let probe = QUERY_SCHEDULER
+            .run(addr, priority, async {
+                let start = Instant::now();
+                a2s_client.info(addr).await?;
+                Ok(start.elapsed())
+            })
+            .await;
+
+        match probe {
+            Ok(rtt) => rtts.push(rtt.as_millis().min(u32::MAX as u128) as u32),
+            Err(QueryError::Cancelled) => return Err(QueryError::Cancelled.into()),
+            // Anything else counts as a lost packet
+            Err(QueryError::A2S(_)) => {}
+        }
+    }
+
+    Ok(summarize(samples, &rtts))
+}
+
+/// Turns the round trips we got back (in the order they came) into stats.
+pub fn summarize(samples: u32, rtts: &[u32]) -> PingStats {
+    let received = rtts.len() as u32;
+    let loss_percent = match samples {
+        0 => 0.0,
+        _ => (samples - received) as f32 / samples as f32 * 100.0,
+    };
+
+    let jitter_ms = match rtts.len() {
+        0 | 1 => 0,
+        len => {
+            let total: u32 = rtts.windows(2).map(|w| w[0].abs_diff(w[1])).sum();
+            total / (len as u32 - 1)
+        }
+    };
+
+    let mut sorted = rtts.to_vec();
+    sorted.sort_unstable();
+    let percentile = |p: f32| match sorted.len() {
+        0 => 0,
+        len => sorted[((len - 1) as f32 * p).round() as usize],
+    };
+
+    let median_ms = percentile(0.5);
+    let mut stats = PingStats {
+        samples,
+        received,
+        min_ms: sorted.first().copied().unwrap_or(0),
+        median_ms,
+        p95_ms: percentile(0.95),
+        jitter_ms,
+        loss_percent,
+        quality: 0,
+        measured_at: now_secs(),
+    };
+    stats.quality = quality(&stats);
+    stats
+}
+
+/// Connection quality from 0 to 100. Latency costs the most, loss hurts more than jitter.
+/// Nothing received at all is always 0.
+fn quality(stats: &PingStats) -> u32 {
+    if stats.received == 0 {
+        return 0;
+    }
+
+    let latency_penalty = (stats.median_ms as f32 / 4.0).min(60.0);
+    let jitter_penalty = (stats.jitter_ms as f32 / 2.0).min(20.0);
+    let loss_penalty = stats.loss_percent * 0.8;
+
+    (100.0 - latency_penalty - jitter_penalty - loss_penalty).clamp(0.0, 100.0) as u32
+}
+
+/// Stores the stats on the server, and the median as its ping.
+async fn store(addr: &str, stats: &PingStats) {
+    let mut server_map = SERVER_MAP.clone().lock_owned().await;
+    if let Some(server) = server_map.get_mut(addr) {
+        server.ping = match stats.received {
+            0 => None,
+            _ => Some(stats.median_ms as i64),
+        };
+        server.ping_stats = Some(stats.clone());
+    }
+}
+
+impl From<PingStats32> for PingStats {
+    fn from(stats: PingStats32) -> Self {
+        PingStats {
+            samples: stats.samples,
+            received: stats.received,
+            min_ms: stats.min_ms,
+            median_ms: stats.median_ms,
+            p95_ms: stats.p95_ms,
+            jitter_ms: stats.jitter_ms,
+            loss_percent: stats.loss_percent,
+            quality: stats.quality,
+            measured_at: stats.measured_at as i64,
+        }
+    }
+}
+
+impl From<PingStats> for PingStats32 {
+    fn from(stats: PingStats) -> Self {
+        PingStats32 {
+            samples: stats.samples,
+            received: stats.received,
+            min_ms: stats.min_ms,
+            median_ms: stats.median_ms,
+            p95_ms: stats.p95_ms,
+            jitter_ms: stats.jitter_ms,
+            loss_percent: stats.loss_percent,
+            quality: stats.quality,
+            measured_at: stats.measured_at as u32,
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Reconciles what the FTL API told us about a server with what the server itself reports
+//! over A2S. The API snapshot can be stale (wrong game port, old steam id after a reinstall),
+//! and that breaks joining and the playerlist, so A2S wins whenever it has a value.
+
+use super::counts;
+use super::integrity;
+use super::now_secs;
+use super::Server;
+use a2s::info::Info;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// Where a server field's current value came from.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum FieldSource {
+    /// The FTL API snapshot.
+    #[default]
+    Api,
+    /// The server's own A2S_INFO response.
+    A2S,
+}
+
+/// Server fields that A2S can correct.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum ServerField {
+    GamePort,
+    SteamId,
+    AppId,
+    Version,
+    Secure,
+}
+
+/// Source of every field we reconcile.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSources {
+    pub game_port: FieldSource,
+    pub steam_id: FieldSource,
+    pub app_id: FieldSource,
+    pub version: FieldSource,
+    pub secure: FieldSource,
+}
+
+impl FieldSources {
+    /// Every field came straight from the server, e.g. custom and LAN servers.
+    pub fn a2s() -> FieldSources {
+        FieldSources {
+            game_port: FieldSource::A2S,
+            steam_id: FieldSource::A2S,
+            app_id: FieldSource::A2S,
+            version: FieldSource::A2S,
+            secure: FieldSource::A2S,
+        }
+    }
+
+    fn get_mut(&mut self, field: ServerField) -> &mut FieldSource {
+        match field {
+            ServerField::GamePort => &mut self.game_port,
+            ServerField::SteamId => &mut self.steam_id,
+            ServerField::AppId => &mut self.app_id,
+            ServerField::Version => &mut self.version,
+            ServerField::Secure => &mut self.secure,
+        }
+    }
+}
+
+/// Applies an A2S_INFO response to a server, reconciling every field A2S can supply.
+/// Fields the server doesn't report (e.g. no EDF steam id) keep their API value.
+pub fn apply_info(server: &mut Server, info: &Info) {
+    // NOTE: @see https://github.com/danlikestocode/ftl-launcher/issues/1
+    // Counts are reconciled against the API, A2S alone can't be trusted
+    counts::apply_info(server, info);
+    integrity::observe_count(server, now_secs());
+    server.map = info.map.clone();
+
+    // For some reason the author of Rust A2S
+    // decided to rename gametype to keywords ?????
+    if let Some(keywords) = &info.extended_server_info.keywords {
+        server.game_type = keywords.clone();
+    }
+
+    let extended = &info.extended_server_info;
+    let sources = &mut server.sources;
+    let mismatches = &mut server.mismatches;
+
+    if let Some(port) = extended.port {
+        let port = port as i64;
+        reconcile(
+            sources,
+            mismatches,
+            ServerField::GamePort,
+            &mut server.game_port,
+            port,
+        );
+    }
+
+    if let Some(steam_id) = extended.steam_id {
+        let steam_id = steam_id.to_string();
+        reconcile(
+            sources,
+            mismatches,
+            ServerField::SteamId,
+            &mut server.steam_id,
+            steam_id,
+        );
+    }
+
+    // The 16 bit app id is truncated for DayZ, the real one is in the low 24 bits
+    if let Some(game_id) = extended.game_id {
+        let app_id = (game_id & 0xFFFFFF) as i64;
+        reconcile(
+            sources,
+            mismatches,
+            ServerField::AppId,
+            &mut server.app_id,
+            app_id,
+        );
+    }
+
+    let version = info.version.clone();
+    reconcile(
+        sources,
+        mismatches,
+        ServerField::Version,
+        &mut server.version,
+        version,
+    );
+    reconcile(
+        sources,
+        mismatches,
+        ServerField::Secure,
+        &mut server.secure,
+        info.vac,
+    );
+
+    // The FTL API doesn't know about passwords, so there's nothing to reconcile
+    server.password_required = info.visibility;
+}
+
+/// Overwrites a field with the value the server reported. If the old value came from the
+/// API, we also note whether the two disagreed. Fields already sourced from A2S keep
+/// whatever mismatch we found last time, until the API hands us a fresh value.
+fn reconcile<T: PartialEq>(
+    sources: &mut FieldSources,
+    mismatches: &mut Vec<ServerField>,
+    field: ServerField,
+    current: &mut T,
+    reported: T,
+) {
+    let source = sources.get_mut(field);
+    if *source == FieldSource::Api {
+        mismatches.retain(|mismatch| *mismatch != field);
+        if *current != reported {
+            mismatches.push(field);
+        }
+    }
+
+    *source = FieldSource::A2S;
+    *current = reported;
+}
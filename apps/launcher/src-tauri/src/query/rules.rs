@@ -0,0 +1,326 @@
+//! Decoding for DayZ's A2S_RULES response.
+//! DayZ (like Arma 3) packs its mod and DLC list into binary rules. The rule names are
+//! two bytes, `[index, total]`, and the values are escaped chunks of one binary blob.
+//! @see https://community.bistudio.com/wiki/Arma_3:_ServerBrowserProtocol3
+
+use super::Mod;
+use a2s::rules::RawRule;
+use anyhow::anyhow;
+use anyhow::Result;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// Everything we could get out of a DayZ rules response.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct DayzRules {
+    pub mods: Vec<RuleMod>,
+    pub dlcs: Vec<RuleDlc>,
+    /// Plain text rules, e.g. `allowedBuild`, `dedicated`, `island`...
+    pub rules: Vec<TextRule>,
+    /// Set if the binary blob was there but we couldn't decode it.
+    pub decode_error: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RuleMod {
+    pub workshop_id: String,
+    pub name: String,
+    /// Mod hash as hex, lets us tell if a local copy matches what the server runs.
+    pub hash: String,
+    pub is_dlc: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct RuleDlc {
+    /// The DLC's bit in the server's DLC flags.
+    pub flag: u32,
+    pub hash: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct TextRule {
+    pub name: String,
+    pub value: String,
+}
+
+impl DayzRules {
+    /// The workshop mods in the same shape as the FTL API's mod list, DLCs are left out.
+    pub fn mod_list(&self) -> Option<Vec<Mod>> {
+        let mods: Vec<Mod> = self
+            .mods
+            .iter()
+            .filter(|dayz_mod| !dayz_mod.is_dlc)
+            .filter_map(|dayz_mod| {
+                Some(Mod {
+                    workshop_id: dayz_mod.workshop_id.parse().ok()?,
+                    name: dayz_mod.name.clone(),
+                })
+            })
+            .collect();
+
+        match mods.is_empty() {
+            true => None,
+            false => Some(mods),
+        }
+    }
+}
+
+/// Splits the raw rules into the binary blob and plain text rules, then decodes the blob.
+pub fn decode(raw_rules: &[RawRule]) -> DayzRules {
+    let mut chunks: Vec<(u8, &[u8])> = Vec::new();
+    let mut decoded = DayzRules::default();
+    let chunk_total = binary_chunk_total(raw_rules);
+
+    for rule in raw_rules {
+        if chunk_total.is_some_and(|total| is_binary_chunk(&rule.name, total)) {
+            chunks.push((rule.name[0], &rule.value));
+        } else {
+            decoded.rules.push(TextRule {
+                name: String::from_utf8_lossy(&rule.name).into_owned(),
+                value: String::from_utf8_lossy(&rule.value).into_owned(),
+            });
+        }
+    }
+
+    if chunks.is_empty() {
+        return decoded;
+    }
+
+    chunks.sort_by_key(|(index, _)| *index);
+    let blob: Vec<u8> = chunks
+        .into_iter()
+        .flat_map(|(_, chunk)| chunk.to_vec())
+        .collect();
+
+    match decode_blob(&unescape(&blob)) {
+        Ok((mods, dlcs)) => {
+            decoded.mods = mods;
+            decoded.dlcs = dlcs;
+        }
+        Err(e) => {
+            println!("Error decoding DayZ rules: {}", e);
+            decoded.decode_error = Some(e.to_string());
+        }
+    }
+
+    decoded
+}
+
+/// Binary chunks are named with two raw bytes, `[index, total]`, the index counting from 1.
+fn is_binary_chunk(name: &[u8], total: u8) -> bool {
+    name.len() == 2 && name[1] == total && name[0] >= 1 && name[0] <= total
+}
+
+/// A two letter text rule can look like a chunk name too, so the blob is the run of
+/// chunks that agree on a total and has every index from 1 to it exactly once.
+fn binary_chunk_total(raw_rules: &[RawRule]) -> Option<u8> {
+    let names: Vec<&[u8]> = raw_rules
+        .iter()
+        .map(|rule| rule.name.as_slice())
+        .filter(|name| name.len() == 2)
+        .collect();
+
+    names
+        .iter()
+        .map(|name| name[1])
+        .filter(|total| *total > 0)
+        .find(|total| {
+            (1..=*total).all(|index| {
+                names
+                    .iter()
+                    .filter(|name| name[0] == index && name[1] == *total)
+                    .count()
+                    == 1
+            })
+        })
+}
+
+/// Undoes the escaping DayZ applies so the blob survives as a C string.
+/// `0x01 0x01` -> `0x01`, `0x01 0x02` -> `0x00`, `0x01 0x03` -> `0xFF`
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte != 0x01 {
+            out.push(byte);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(0x01) => out.push(0x01),
+            Some(0x02) => out.push(0x00),
+            Some(0x03) => out.push(0xFF),
+            Some(&other) => {
+                out.push(byte);
+                out.push(other);
+            }
+            None => out.push(byte),
+        }
+    }
+
+    out
+}
+
+/// Reads the decoded blob, layout is:
+/// `version u8, overflow u8, dlc flags u16, dlc hashes u32 * set bits,
+/// mod count u8, mods { hash u32, info u8, workshop id, name len u8, name }`
+fn decode_blob(data: &[u8]) -> Result<(Vec<RuleMod>, Vec<RuleDlc>)> {
+    let mut reader = BlobReader { data, position: 0 };
+
+    let _version = reader.u8()?;
+    let _overflow = reader.u8()?;
+    let dlc_flags = reader.uint(2)? as u32;
+
+    let mut dlcs = Vec::new();
+    for bit in 0..16 {
+        let flag = 1u32 << bit;
+        if dlc_flags & flag != 0 {
+            dlcs.push(RuleDlc {
+                flag,
+                hash: format!("{:08x}", reader.uint(4)?),
+            });
+        }
+    }
+
+    let mod_count = reader.u8()?;
+    let mut mods = Vec::with_capacity(mod_count as usize);
+    for _ in 0..mod_count {
+        let hash = reader.uint(4)?;
+
+        // Low nibble is the workshop id length, 0x10 marks a DLC
+        let info = reader.u8()?;
+        let workshop_id = reader.uint((info & 0x0F) as usize)?;
+
+        let name_len = reader.u8()? as usize;
+        let name = String::from_utf8_lossy(reader.bytes(name_len)?).into_owned();
+
+        mods.push(RuleMod {
+            workshop_id: workshop_id.to_string(),
+            name,
+            hash: format!("{:08x}", hash),
+            is_dlc: info & 0x10 != 0,
+        });
+    }
+
+    Ok((mods, dlcs))
+}
+
+struct BlobReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BlobReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.position + len;
+        if end > self.data.len() {
+            return Err(anyhow!(
+                "Unexpected end of rules data at byte {}",
+                self.position
+            ));
+        }
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Reads a little endian unsigned int of up to 8 bytes.
+    fn uint(&mut self, len: usize) -> Result<u64> {
+        if len > 8 {
+            return Err(anyhow!("Can't read a {} byte integer", len));
+        }
+
+        Ok(self
+            .bytes(len)?
+            .iter()
+            .rev()
+            .fold(0u64, |acc, byte| (acc << 8) | *byte as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &[u8], value: &[u8]) -> RawRule {
+        RawRule {
+            name: name.to_vec(),
+            value: value.to_vec(),
+        }
+    }
+
+    /// Version, overflow, no DLCs, one mod: hash, 4 byte workshop id, name. Escaped.
+    fn blob() -> Vec<u8> {
+        let mut blob = vec![0x02, 0x00, 0x00, 0x00, 0x01];
+        blob.extend_from_slice(&[0xEF, 0xBE, 0xAD, 0xDE]);
+        blob.push(0x04);
+        blob.extend_from_slice(&1559212036u32.to_le_bytes());
+        blob.push(2);
+        blob.extend_from_slice(b"CF");
+        escape(&blob)
+    }
+
+    /// What DayZ does before sending the blob, see `unescape`.
+    fn escape(data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .flat_map(|byte| match byte {
+                0x01 => vec![0x01, 0x01],
+                0x00 => vec![0x01, 0x02],
+                0xFF => vec![0x01, 0x03],
+                other => vec![*other],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decodes_chunks_out_of_order() {
+        let blob = blob();
+        let (first, second) = blob.split_at(6);
+        let decoded = decode(&[
+            rule(&[2, 2], second),
+            rule(b"island", b"chernarusplus"),
+            rule(&[1, 2], first),
+        ]);
+
+        assert_eq!(decoded.decode_error, None);
+        assert_eq!(
+            decoded.mods,
+            vec![RuleMod {
+                workshop_id: "1559212036".to_string(),
+                name: "CF".to_string(),
+                hash: "deadbeef".to_string(),
+                is_dlc: false,
+            }]
+        );
+        assert_eq!(decoded.rules.len(), 1);
+    }
+
+    #[test]
+    fn keeps_two_letter_text_rules() {
+        let decoded = decode(&[rule(b"ab", b"1"), rule(b"aa", b"2"), rule(&[1, 1], &blob())]);
+
+        assert_eq!(decoded.decode_error, None);
+        assert_eq!(decoded.mods.len(), 1);
+        let names: Vec<&str> = decoded
+            .rules
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["ab", "aa"]);
+    }
+
+    #[test]
+    fn no_blob_without_a_complete_run() {
+        let decoded = decode(&[rule(b"ab", b"1"), rule(&[1, 2], &blob())]);
+
+        assert!(decoded.mods.is_empty());
+        assert_eq!(decoded.decode_error, None);
+        assert_eq!(decoded.rules.len(), 2);
+    }
+}
@@ -0,0 +1,444 @@
+use a2s::errors::Error as A2SError;
+use lazy_static::lazy_static;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+use tokio::sync::Notify;
+
+/// How many of the most recent query outcomes we look at when deciding to throttle.
+const OUTCOME_WINDOW: usize = 50;
+
+/// We need at least this many outcomes before we trust the timeout ratio.
+const MIN_OUTCOMES: usize = 20;
+
+/// If more than this fraction of recent queries timed out, we halve the concurrency.
+const BACKOFF_RATIO: f64 = 0.25;
+
+/// If less than this fraction of recent queries timed out, we slowly ramp back up.
+const RECOVER_RATIO: f64 = 0.05;
+
+lazy_static! {
+    /// The one scheduler every A2S query from the frontend goes through.
+    /// Starts at 10 concurrent queries, the frontend can tune this at runtime.
+    pub static ref QUERY_SCHEDULER: Arc<QueryScheduler> = Arc::new(QueryScheduler::new(10));
+}
+
+/// Priority classes for server queries, highest priority first.
+/// Visible rows always jump the line, then favorites, then everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, specta::Type)]
+pub enum QueryPriority {
+    Visible,
+    Favorite,
+    Background,
+}
+
+impl QueryPriority {
+    fn index(&self) -> usize {
+        match self {
+            QueryPriority::Visible => 0,
+            QueryPriority::Favorite => 1,
+            QueryPriority::Background => 2,
+        }
+    }
+}
+
+/// Errors that can come out of a scheduled query.
+#[derive(Debug)]
+pub enum QueryError {
+    /// The request was cancelled before (or while) it ran, e.g. the row scrolled away.
+    Cancelled,
+    /// The query itself failed.
+    A2S(A2SError),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Cancelled => write!(f, "Query was cancelled"),
+            QueryError::A2S(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A request waiting in one of the priority queues.
+struct Waiter {
+    id: u64,
+    key: String,
+    tx: oneshot::Sender<QueryPermit>,
+}
+
+struct SchedulerState {
+    /// What the user asked for.
+    max_concurrency: usize,
+    /// What we are actually allowing right now, after adaptive throttling.
+    effective_concurrency: usize,
+    in_flight: usize,
+    queues: [VecDeque<Waiter>; 3],
+    /// Cancellation handles for requests that are currently running, keyed by request key.
+    running: HashMap<String, Vec<(u64, Arc<Notify>)>>,
+    /// Recent outcomes, `true` means the query timed out.
+    outcomes: VecDeque<bool>,
+    next_id: u64,
+}
+
+/// Priority query scheduler. Replaces the old destroy/recreate semaphore.
+///
+/// Requests are queued per priority class and handed a `QueryPermit` once there is
+/// room. Any request can be cancelled by key (the server's query address) whether it
+/// is still queued or already running. Concurrency can be changed at runtime, and
+/// backs off on its own when timeouts start to spike.
+pub struct QueryScheduler {
+    // NOTE: This is a std mutex on purpose, permits release from `Drop`
+    // and we never hold this across an await 😎
+    state: Mutex<SchedulerState>,
+}
+
+/// Held while a query is running. Dropping it frees the slot for the next request.
+pub struct QueryPermit {
+    scheduler: Arc<QueryScheduler>,
+    id: u64,
+    key: String,
+    cancel: Arc<Notify>,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.scheduler.release(self.id, &self.key);
+    }
+}
+
+impl QueryScheduler {
+    pub fn new(max_concurrency: usize) -> QueryScheduler {
+        let max_concurrency = max_concurrency.max(1);
+        QueryScheduler {
+            state: Mutex::new(SchedulerState {
+                max_concurrency,
+                effective_concurrency: max_concurrency,
+                in_flight: 0,
+                queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+                running: HashMap::new(),
+                outcomes: VecDeque::with_capacity(OUTCOME_WINDOW),
+                next_id: 0,
+            }),
+        }
+    }
+
+    /// Runs `query` once a slot is free for the given priority class.
+    /// The query is dropped mid-flight if `cancel` is called with the same key.
+    pub async fn run<T, F>(
+        self: &Arc<Self>,
+        key: &str,
+        priority: QueryPriority,
+        query: F,
+    ) -> Result<T, QueryError>
+    where
+        F: Future<Output = a2s::errors::Result<T>>,
+    {
+        let permit = self.acquire(key, priority).await?;
+        let cancel = permit.cancel.clone();
+
+        let result = tokio::select! {
+            _ = cancel.notified() => return Err(QueryError::Cancelled),
+            result = query => result,
+        };
+
+        self.record(matches!(result, Err(A2SError::ErrTimeout)));
+        drop(permit);
+
+        result.map_err(QueryError::A2S)
+    }
+
+    /// Waits in line for a permit. Errors if the request is cancelled while waiting.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        key: &str,
+        priority: QueryPriority,
+    ) -> Result<QueryPermit, QueryError> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            state.queues[priority.index()].push_back(Waiter {
+                id,
+                key: key.to_owned(),
+                tx,
+            });
+        }
+        self.dispatch();
+
+        // If our sender gets dropped, someone cancelled us while we were waiting
+        rx.await.map_err(|_| QueryError::Cancelled)
+    }
+
+    /// Cancels every queued or running request with this key.
+    pub fn cancel(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        // Dropping the waiters drops their senders, which wakes them up with an error
+        for queue in state.queues.iter_mut() {
+            queue.retain(|waiter| waiter.key != key);
+        }
+
+        if let Some(running) = state.running.get(key) {
+            for (_, cancel) in running {
+                cancel.notify_one();
+            }
+        }
+    }
+
+    /// Sets the max number of concurrent queries. This also resets any adaptive backoff.
+    pub fn set_max_concurrency(self: &Arc<Self>, max_concurrency: usize) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.max_concurrency = max_concurrency.max(1);
+            state.effective_concurrency = state.max_concurrency;
+            state.outcomes.clear();
+        }
+        self.dispatch();
+    }
+
+    /// Records the outcome of a query and adjusts the effective concurrency.
+    /// Halves on a timeout spike, creeps back up by one while things look healthy.
+    fn record(self: &Arc<Self>, timed_out: bool) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.outcomes.len() == OUTCOME_WINDOW {
+                state.outcomes.pop_front();
+            }
+            state.outcomes.push_back(timed_out);
+
+            if state.outcomes.len() >= MIN_OUTCOMES {
+                let timeouts = state.outcomes.iter().filter(|t| **t).count();
+                let ratio = timeouts as f64 / state.outcomes.len() as f64;
+
+                if ratio > BACKOFF_RATIO && state.effective_concurrency > 1 {
+                    state.effective_concurrency = (state.effective_concurrency / 2).max(1);
+                    state.outcomes.clear();
+                    println!(
+                        "QueryScheduler: ⚠️ Timeouts spiking ({:.0}%), backing off to {}",
+                        ratio * 100.0,
+                        state.effective_concurrency
+                    );
+                } else if ratio < RECOVER_RATIO
+                    && state.effective_concurrency < state.max_concurrency
+                {
+                    state.effective_concurrency += 1;
+                    state.outcomes.clear();
+                }
+            }
+        }
+        self.dispatch();
+    }
+
+    /// Frees a slot and lets the next request in line through.
+    fn release(self: &Arc<Self>, id: u64, key: &str) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.in_flight = state.in_flight.saturating_sub(1);
+            if let Some(running) = state.running.get_mut(key) {
+                running.retain(|(running_id, _)| *running_id != id);
+                if running.is_empty() {
+                    state.running.remove(key);
+                }
+            }
+        }
+        self.dispatch();
+    }
+
+    /// Hands out permits in priority order while there is room.
+    fn dispatch(self: &Arc<Self>) {
+        let mut granted: Vec<(oneshot::Sender<QueryPermit>, QueryPermit)> = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            while state.in_flight < state.effective_concurrency {
+                let waiter = state.queues.iter_mut().find_map(|queue| queue.pop_front());
+                let Some(waiter) = waiter else {
+                    break;
+                };
+
+                // Whoever was waiting gave up, don't bother
+                if waiter.tx.is_closed() {
+                    continue;
+                }
+
+                let cancel = Arc::new(Notify::new());
+                state
+                    .running
+                    .entry(waiter.key.clone())
+                    .or_default()
+                    .push((waiter.id, cancel.clone()));
+                state.in_flight += 1;
+
+                granted.push((
+                    waiter.tx,
+                    QueryPermit {
+                        scheduler: self.clone(),
+                        id: waiter.id,
+                        key: waiter.key,
+                        cancel,
+                    },
+                ));
+            }
+        }
+
+        // Send outside the lock, if the receiver is gone the permit
+        // drops right here and releases its slot.
+        for (tx, permit) in granted {
+            let _ = tx.send(permit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use tokio::task;
+
+    fn effective_concurrency(scheduler: &QueryScheduler) -> usize {
+        scheduler.state.lock().unwrap().effective_concurrency
+    }
+
+    fn queued(scheduler: &QueryScheduler) -> usize {
+        let state = scheduler.state.lock().unwrap();
+        state.queues.iter().map(VecDeque::len).sum()
+    }
+
+    async fn timeout() -> a2s::errors::Result<()> {
+        Err(A2SError::ErrTimeout)
+    }
+
+    async fn answer() -> a2s::errors::Result<()> {
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn visible_goes_before_background() {
+        let scheduler = Arc::new(QueryScheduler::new(1));
+        let busy = scheduler
+            .acquire("busy", QueryPriority::Background)
+            .await
+            .unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // Background gets in line first
+        let mut waiting = Vec::new();
+        for (key, priority) in [
+            ("background", QueryPriority::Background),
+            ("visible", QueryPriority::Visible),
+        ] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            waiting.push(task::spawn(async move {
+                let _permit = scheduler.acquire(key, priority).await.unwrap();
+                order.lock().unwrap().push(key);
+            }));
+            task::yield_now().await;
+        }
+        assert_eq!(queued(&scheduler), 2);
+
+        drop(busy);
+        for waiter in waiting {
+            waiter.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["visible", "background"]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_key_never_runs() {
+        let scheduler = Arc::new(QueryScheduler::new(1));
+        let busy = scheduler
+            .acquire("busy", QueryPriority::Visible)
+            .await
+            .unwrap();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        let waiting = task::spawn({
+            let scheduler = scheduler.clone();
+            let ran = ran.clone();
+            async move {
+                let query = async {
+                    ran.store(true, Ordering::SeqCst);
+                    Ok(())
+                };
+                scheduler
+                    .run("1.1.1.1:2303", QueryPriority::Background, query)
+                    .await
+            }
+        });
+        task::yield_now().await;
+        assert_eq!(queued(&scheduler), 1);
+
+        scheduler.cancel("1.1.1.1:2303");
+        drop(busy);
+
+        let result = waiting.await.unwrap();
+        assert!(matches!(result, Err(QueryError::Cancelled)));
+        assert!(!ran.load(Ordering::SeqCst));
+        assert_eq!(queued(&scheduler), 0);
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_running_query() {
+        let scheduler = Arc::new(QueryScheduler::new(1));
+
+        let running = task::spawn({
+            let scheduler = scheduler.clone();
+            async move {
+                let query = std::future::pending::<a2s::errors::Result<()>>();
+                scheduler
+                    .run("1.1.1.1:2303", QueryPriority::Visible, query)
+                    .await
+            }
+        });
+        task::yield_now().await;
+
+        scheduler.cancel("1.1.1.1:2303");
+        let result = running.await.unwrap();
+        assert!(matches!(result, Err(QueryError::Cancelled)));
+
+        // The slot is free again
+        let next = scheduler.run("2.2.2.2:2303", QueryPriority::Visible, answer());
+        assert!(next.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn halves_on_timeouts_and_creeps_back() {
+        let scheduler = Arc::new(QueryScheduler::new(8));
+
+        for _ in 0..MIN_OUTCOMES {
+            let _ = scheduler
+                .run("a", QueryPriority::Background, timeout())
+                .await;
+        }
+        assert_eq!(effective_concurrency(&scheduler), 4);
+
+        for _ in 0..MIN_OUTCOMES {
+            let _ = scheduler
+                .run("a", QueryPriority::Background, timeout())
+                .await;
+        }
+        assert_eq!(effective_concurrency(&scheduler), 2);
+
+        // One step at a time, and never past the max
+        for expected in [3, 4, 5, 6, 7, 8, 8] {
+            for _ in 0..MIN_OUTCOMES {
+                let _ = scheduler
+                    .run("a", QueryPriority::Background, answer())
+                    .await;
+            }
+            assert_eq!(effective_concurrency(&scheduler), expected);
+        }
+    }
+}
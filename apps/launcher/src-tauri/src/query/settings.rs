@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// User settings for what `get_server_list` hands back.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(default)]
+pub struct QuerySettings {
+    /// Leave out servers that are the same box as another listing.
+    pub hide_duplicates: bool,
+    /// Leave out servers tagged as suspicious, e.g. fake player counts.
+    pub hide_flagged: bool,
+    /// MaxMind format database (e.g. GeoLite2-City.mmdb) used to locate servers.
+    pub geoip_db_path: Option<String>,
+    /// Where the user is, for server distances.
+    pub user_location: Option<Coordinates>,
+    /// Re-measure favorites' pings in the background.
+    pub background_ping: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn query_get_settings(app_handle: AppHandle) -> Result<QuerySettings, String> {
+    load(&app_handle).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn query_set_settings(
+    settings: QuerySettings,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let path = settings_path(&app_handle).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// Loads the query settings, defaults if they were never saved.
+pub fn load(app_handle: &AppHandle) -> Result<QuerySettings> {
+    let path = settings_path(app_handle)?;
+    if !path.exists() {
+        return Ok(QuerySettings::default());
+    }
+
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    let dir = app_handle.path().app_config_dir()?;
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    Ok(dir.join("query_settings.json"))
+}
@@ -0,0 +1,123 @@
+use a2s::errors::Error as A2SError;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::io::ErrorKind;
+
+/// First retry after a failure waits this long, then doubles for every failure after.
+const RETRY_BASE_SECS: i64 = 30;
+
+/// We never wait longer than this between retries.
+const RETRY_MAX_SECS: i64 = 60 * 60;
+
+/// Whether we can reach a server, and how the last query went.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, specta::Type)]
+pub enum ServerStatus {
+    /// We haven't tried yet.
+    #[default]
+    NeverQueried,
+    /// Last query came back fine.
+    Online,
+    /// Last query never got a response.
+    Timeout,
+    /// The server (or something in the way) actively refused us.
+    Refused,
+    /// We got a response, but it wasn't valid A2S.
+    Malformed,
+}
+
+impl ServerStatus {
+    /// Maps an A2S error to the status it means for the server.
+    pub fn from_error(error: &A2SError) -> ServerStatus {
+        match error {
+            A2SError::ErrTimeout => ServerStatus::Timeout,
+            A2SError::Io(e) => match e.kind() {
+                // Windows reports ICMP port unreachable as a connection reset
+                ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::AddrNotAvailable => ServerStatus::Refused,
+                ErrorKind::TimedOut | ErrorKind::WouldBlock => ServerStatus::Timeout,
+                ErrorKind::UnexpectedEof | ErrorKind::InvalidData => ServerStatus::Malformed,
+                _ => ServerStatus::Refused,
+            },
+            _ => ServerStatus::Malformed,
+        }
+    }
+}
+
+/// Reachability state for a server. Replaces the old `ping: Some(99999)` sentinel.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerHealth {
+    pub status: ServerStatus,
+    /// Unix timestamp (seconds) of the last successful query.
+    pub last_success: Option<i64>,
+    /// Unix timestamp (seconds) of the last query, successful or not.
+    pub last_attempt: Option<i64>,
+    pub consecutive_failures: u32,
+    /// Unix timestamp (seconds) before which we won't query this server again.
+    pub next_retry_at: Option<i64>,
+}
+
+/// 32 Bit Server Health Data Structure (JS can't handle i64)
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct ServerHealth32 {
+    pub status: ServerStatus,
+    pub last_success: Option<u32>,
+    pub last_attempt: Option<u32>,
+    pub consecutive_failures: u32,
+    pub next_retry_at: Option<u32>,
+}
+
+impl ServerHealth {
+    /// Whether this server is due for a query. Online and never queried servers
+    /// always are, failing servers wait out their backoff first.
+    pub fn should_query(&self, now: i64) -> bool {
+        match self.next_retry_at {
+            Some(next_retry_at) => now >= next_retry_at,
+            None => true,
+        }
+    }
+
+    pub fn record_success(&mut self, now: i64) {
+        self.status = ServerStatus::Online;
+        self.last_success = Some(now);
+        self.last_attempt = Some(now);
+        self.consecutive_failures = 0;
+        self.next_retry_at = None;
+    }
+
+    /// Records a failed query and pushes the next retry out, doubling every time.
+    pub fn record_failure(&mut self, status: ServerStatus, now: i64) {
+        self.status = status;
+        self.last_attempt = Some(now);
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        let exponent = (self.consecutive_failures - 1).min(16);
+        let backoff = (RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS);
+        self.next_retry_at = Some(now + backoff);
+    }
+}
+
+impl From<ServerHealth32> for ServerHealth {
+    fn from(health: ServerHealth32) -> Self {
+        ServerHealth {
+            status: health.status,
+            last_success: health.last_success.map(|t| t as i64),
+            last_attempt: health.last_attempt.map(|t| t as i64),
+            consecutive_failures: health.consecutive_failures,
+            next_retry_at: health.next_retry_at.map(|t| t as i64),
+        }
+    }
+}
+
+impl From<ServerHealth> for ServerHealth32 {
+    fn from(health: ServerHealth) -> Self {
+        ServerHealth32 {
+            status: health.status,
+            last_success: health.last_success.map(|t| t as u32),
+            last_attempt: health.last_attempt.map(|t| t as u32),
+            consecutive_failures: health.consecutive_failures,
+            next_retry_at: health.next_retry_at.map(|t| t as u32),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+//! Everything the launcher needs from Steam, behind a trait. Commands talk to a
+//! `SteamBackend` instead of `steamworks::Client`, so the mod queue, repair and launch
+//! logic can run against the in-memory fake (`fake-steam` feature) on a machine without Steam.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use steamworks::AppIDs;
+use steamworks::AppId;
+use steamworks::CallbackHandle;
+use steamworks::Client;
+use steamworks::DownloadItemResult;
+use steamworks::ItemState;
+use steamworks::PublishedFileId;
+use steamworks::QueryResults;
+use steamworks::SteamId;
+use steamworks::UGCQueryType;
+use steamworks::UGCStatisticType;
+use steamworks::UGCType;
+use tokio::sync::oneshot;
+use tokio::time;
+
+/// How long we wait on Steam to answer a workshop query.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Steam returns at most this many items per UGC query page.
+pub const QUERY_PAGE_SIZE: usize = 50;
+
+/// How many days of votes count towards "trending".
+const TRENDING_DAYS: u32 = 7;
+
+/// Called once Steam gets back to us about a subscribe, unsubscribe or delete.
+pub type SteamCallback = Box<dyn FnOnce(Result<(), String>) + Send + 'static>;
+
+/// Called once a workshop query comes back.
+pub type QueryCallback = Box<dyn FnOnce(Result<Vec<WorkshopItem>, String>) + Send + 'static>;
+
+/// Called once a workshop search comes back.
+pub type SearchCallback = Box<dyn FnOnce(Result<WorkshopPage, String>) + Send + 'static>;
+
+/// Something Steam told us about a workshop item we're getting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadEvent {
+    /// A subscribe went through (or didn't).
+    Subscribed {
+        published_file_id: u64,
+        result: Result<(), String>,
+    },
+    /// A download finished (or failed, e.g. the item is gone, banned or the disk is full).
+    Downloaded {
+        published_file_id: u64,
+        result: Result<(), String>,
+    },
+}
+
+/// Gets every `DownloadEvent`, called from whatever thread runs the callbacks.
+pub type DownloadListener = Arc<dyn Fn(DownloadEvent) + Send + Sync + 'static>;
+
+/// Where an installed workshop item lives on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallInfo {
+    pub folder: String,
+    pub size_on_disk: u64,
+    /// Unix timestamp (seconds) of when the installed version was last updated.
+    pub timestamp: u32,
+}
+
+/// Workshop item details, as returned by a UGC query.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WorkshopItem {
+    pub published_file_id: u64,
+    pub title: String,
+    pub description: String,
+    pub owner_steam_id: u64,
+    pub time_created: u32,
+    pub time_updated: u32,
+    pub time_added_to_user_list: u32,
+    pub banned: bool,
+    pub accepted_for_use: bool,
+    pub tags: Vec<String>,
+    pub tags_truncated: bool,
+    pub file_size: u32,
+    pub url: String,
+    pub num_upvotes: u32,
+    pub num_downvotes: u32,
+    pub score: f32,
+    pub num_children: u32,
+    /// Items this one requires, only filled in when Steam returns them.
+    pub children: Vec<u64>,
+    pub preview_url: Option<String>,
+    pub subscriptions: u64,
+}
+
+/// How workshop search results are ordered.
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, specta::Type,
+)]
+pub enum WorkshopSort {
+    #[default]
+    Trending,
+    MostSubscribed,
+    RecentlyUpdated,
+}
+
+/// A search over every workshop item of an app.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WorkshopSearch {
+    pub app_id: u32,
+    /// Matched against titles and descriptions.
+    pub text: Option<String>,
+    /// Items need every one of these.
+    pub tags: Vec<String>,
+    pub sort: WorkshopSort,
+    /// Starts at 1, `QUERY_PAGE_SIZE` items a page.
+    pub page: u32,
+}
+
+/// One page of workshop search results.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct WorkshopPage {
+    pub items: Vec<WorkshopItem>,
+    /// Matches over all pages.
+    pub total_results: u32,
+}
+
+/// A player Steam knows is on a game server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourcePlayer {
+    pub steam_id: u64,
+    pub name: String,
+    pub nick_name: Option<String>,
+    pub avatar: Option<Vec<u8>>,
+}
+
+/// The UGC, apps, friends and user operations the launcher uses.
+pub trait SteamBackend: Send + Sync {
+    // Apps
+    fn is_app_installed(&self, app_id: u32) -> bool;
+    fn app_install_dir(&self, app_id: u32) -> String;
+
+    // UGC
+    fn subscribe_item(&self, published_file_id: u64, callback: SteamCallback);
+    fn unsubscribe_item(&self, published_file_id: u64, callback: SteamCallback);
+    fn delete_item(&self, published_file_id: u64, callback: SteamCallback);
+    /// Starts (or verifies) a download, false if Steam refused, e.g. an invalid id.
+    fn download_item(&self, published_file_id: u64, high_priority: bool) -> bool;
+    fn item_install_info(&self, published_file_id: u64) -> Option<InstallInfo>;
+    /// Whether Steam knows there's a newer version than the one installed.
+    fn item_needs_update(&self, published_file_id: u64) -> bool;
+    /// `(bytes_downloaded, bytes_total)`, only while a download is in progress.
+    fn item_download_info(&self, published_file_id: u64) -> Option<(u64, u64)>;
+    fn subscribed_items(&self) -> Vec<u64>;
+    /// Sends download results to `listener` until the backend is dropped.
+    /// Subscribe results come back through `subscribe_item`'s callback instead.
+    fn watch_downloads(&self, listener: DownloadListener);
+    /// Queries workshop details for the given items. Errors if the query couldn't be created.
+    fn query_items(
+        &self,
+        published_file_ids: Vec<u64>,
+        callback: QueryCallback,
+    ) -> Result<(), String>;
+    /// Searches the workshop. Errors if the query couldn't be created.
+    fn search_workshop(
+        &self,
+        search: WorkshopSearch,
+        callback: SearchCallback,
+    ) -> Result<(), String>;
+
+    // Friends & User
+    fn user_name(&self) -> String;
+    fn user_steam_id(&self) -> u64;
+    fn user_avatar(&self) -> Option<Vec<u8>>;
+    fn players_from_source(&self, server_steam_id: u64) -> Vec<SourcePlayer>;
+
+    /// Whether Steam is still there and logged on. Goes false when Steam exits or the user logs out.
+    fn is_alive(&self) -> bool;
+
+    /// Runs pending callbacks. Steamworks callbacks are pumped by the `SingleClient`,
+    /// so only backends that queue their own callbacks need this.
+    fn run_callbacks(&self) {}
+}
+
+/// The real thing, backed by a mounted `steamworks::Client`.
+pub struct SteamworksBackend {
+    client: Client,
+    /// Registered callbacks stop when their handle drops, so we hang on to them.
+    callbacks: Mutex<Vec<CallbackHandle>>,
+}
+
+impl SteamworksBackend {
+    pub fn new(client: Client) -> SteamworksBackend {
+        SteamworksBackend {
+            client,
+            callbacks: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl SteamBackend for SteamworksBackend {
+    fn is_app_installed(&self, app_id: u32) -> bool {
+        self.client.apps().is_app_installed(AppId(app_id))
+    }
+
+    fn app_install_dir(&self, app_id: u32) -> String {
+        self.client.apps().app_install_dir(AppId(app_id))
+    }
+
+    fn subscribe_item(&self, published_file_id: u64, callback: SteamCallback) {
+        self.client
+            .ugc()
+            .subscribe_item(PublishedFileId(published_file_id), move |result| {
+                callback(result.map_err(|e| e.to_string()))
+            });
+    }
+
+    fn unsubscribe_item(&self, published_file_id: u64, callback: SteamCallback) {
+        self.client
+            .ugc()
+            .unsubscribe_item(PublishedFileId(published_file_id), move |result| {
+                callback(result.map_err(|e| e.to_string()))
+            });
+    }
+
+    fn delete_item(&self, published_file_id: u64, callback: SteamCallback) {
+        self.client
+            .ugc()
+            .delete_item(PublishedFileId(published_file_id), move |result| {
+                callback(result.map_err(|e| e.to_string()))
+            });
+    }
+
+    fn download_item(&self, published_file_id: u64, high_priority: bool) -> bool {
+        self.client
+            .ugc()
+            .download_item(PublishedFileId(published_file_id), high_priority)
+    }
+
+    fn item_install_info(&self, published_file_id: u64) -> Option<InstallInfo> {
+        self.client
+            .ugc()
+            .item_install_info(PublishedFileId(published_file_id))
+            .map(|info| InstallInfo {
+                folder: info.folder,
+                size_on_disk: info.size_on_disk,
+                timestamp: info.timestamp,
+            })
+    }
+
+    fn item_needs_update(&self, published_file_id: u64) -> bool {
+        self.client
+            .ugc()
+            .item_state(PublishedFileId(published_file_id))
+            .contains(ItemState::NEEDS_UPDATE)
+    }
+
+    fn item_download_info(&self, published_file_id: u64) -> Option<(u64, u64)> {
+        self.client
+            .ugc()
+            .item_download_info(PublishedFileId(published_file_id))
+    }
+
+    fn subscribed_items(&self) -> Vec<u64> {
+        self.client
+            .ugc()
+            .subscribed_items()
+            .into_iter()
+            .map(|item| item.0)
+            .collect()
+    }
+
+    fn query_items(
+        &self,
+        published_file_ids: Vec<u64>,
+        callback: QueryCallback,
+    ) -> Result<(), String> {
+        let ids = published_file_ids
+            .into_iter()
+            .map(PublishedFileId)
+            .collect();
+        let query = self
+            .client
+            .ugc()
+            .query_items(ids)
+            .map_err(|e| e.to_string())?;
+
+        // Children are the items this one requires
+        let query = query.set_return_children(true);
+        query.fetch(move |results| {
+            let results = results
+                .map_err(|e| e.to_string())
+                .map(|results| workshop_items(&results));
+
+            callback(results);
+        });
+
+        Ok(())
+    }
+
+    fn search_workshop(
+        &self,
+        search: WorkshopSearch,
+        callback: SearchCallback,
+    ) -> Result<(), String> {
+        let query_type = match search.sort {
+            WorkshopSort::Trending => UGCQueryType::RankedByTrend,
+            WorkshopSort::MostSubscribed => UGCQueryType::RankedByTotalUniqueSubscriptions,
+            WorkshopSort::RecentlyUpdated => UGCQueryType::RankedByLastUpdatedDate,
+        };
+        let mut query = self
+            .client
+            .ugc()
+            .query_all(
+                query_type,
+                UGCType::Items,
+                AppIDs::ConsumerAppId(AppId(search.app_id)),
+                search.page,
+            )
+            .map_err(|e| e.to_string())?
+            .set_return_children(true);
+
+        if search.sort == WorkshopSort::Trending {
+            query = query.set_ranked_by_trend_days(TRENDING_DAYS);
+        }
+        if let Some(text) = &search.text {
+            query = query.set_search_text(text);
+        }
+        for tag in &search.tags {
+            query = query.require_tag(tag);
+        }
+
+        query.fetch(move |results| {
+            let results = results
+                .map_err(|e| e.to_string())
+                .map(|results| WorkshopPage {
+                    items: workshop_items(&results),
+                    total_results: results.total_results(),
+                });
+
+            callback(results);
+        });
+
+        Ok(())
+    }
+
+    fn watch_downloads(&self, listener: DownloadListener) {
+        let downloaded = self
+            .client
+            .register_callback(move |downloaded: DownloadItemResult| {
+                listener(DownloadEvent::Downloaded {
+                    published_file_id: downloaded.published_file_id.0,
+                    result: match downloaded.error {
+                        Some(e) => Err(e.to_string()),
+                        None => Ok(()),
+                    },
+                })
+            });
+
+        self.callbacks.lock().unwrap().push(downloaded);
+    }
+
+    fn user_name(&self) -> String {
+        self.client.friends().name()
+    }
+
+    fn user_steam_id(&self) -> u64 {
+        self.client.user().steam_id().raw()
+    }
+
+    fn user_avatar(&self) -> Option<Vec<u8>> {
+        self.client.friends().medium_avatar()
+    }
+
+    fn is_alive(&self) -> bool {
+        self.client.user().logged_on()
+    }
+
+    fn players_from_source(&self, server_steam_id: u64) -> Vec<SourcePlayer> {
+        self.client
+            .friends()
+            .get_friends_from_source(SteamId::from_raw(server_steam_id))
+            .into_iter()
+            .map(|user| SourcePlayer {
+                steam_id: user.id().raw(),
+                name: user.name(),
+                nick_name: user.nick_name(),
+                avatar: user.medium_avatar(),
+            })
+            .collect()
+    }
+}
+
+/// Converts a page of UGC query results.
+fn workshop_items(results: &QueryResults) -> Vec<WorkshopItem> {
+    (0..results.returned_results())
+        .filter_map(|i| results.get(i).map(|item| (i, item)))
+        .map(|(i, item)| WorkshopItem {
+            published_file_id: item.published_file_id.0,
+            title: item.title,
+            description: item.description,
+            owner_steam_id: item.owner.raw(),
+            time_created: item.time_created,
+            time_updated: item.time_updated,
+            time_added_to_user_list: item.time_added_to_user_list,
+            banned: item.banned,
+            accepted_for_use: item.accepted_for_use,
+            tags: item.tags,
+            tags_truncated: item.tags_truncated,
+            file_size: item.file_size,
+            url: item.url,
+            num_upvotes: item.num_upvotes,
+            num_downvotes: item.num_downvotes,
+            score: item.score,
+            num_children: item.num_children,
+            children: results
+                .get_children(i)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|child| child.0)
+                .collect(),
+            preview_url: results.preview_url(i),
+            subscriptions: results
+                .statistic(i, UGCStatisticType::Subscriptions)
+                .unwrap_or(0),
+        })
+        .collect()
+}
+
+/// Queries workshop details and waits for Steam to answer.
+pub async fn fetch_items(
+    backend: &dyn SteamBackend,
+    published_file_ids: Vec<u64>,
+) -> Result<Vec<WorkshopItem>, String> {
+    let (sender, receiver) = oneshot::channel();
+    backend.query_items(
+        published_file_ids,
+        Box::new(move |result| {
+            // Nobody waiting for the answer is fine
+            let _ = sender.send(result);
+        }),
+    )?;
+
+    time::timeout(QUERY_TIMEOUT, receiver)
+        .await
+        .map_err(|_| "Timed out waiting for Steam!".to_string())?
+        .map_err(|_| "Steam dropped the query!".to_string())?
+}
+
+/// Searches the workshop and waits for Steam to answer.
+pub async fn search_workshop(
+    backend: &dyn SteamBackend,
+    search: WorkshopSearch,
+) -> Result<WorkshopPage, String> {
+    let (sender, receiver) = oneshot::channel();
+    backend.search_workshop(
+        search,
+        Box::new(move |result| {
+            // Nobody waiting for the answer is fine
+            let _ = sender.send(result);
+        }),
+    )?;
+
+    time::timeout(QUERY_TIMEOUT, receiver)
+        .await
+        .map_err(|_| "Timed out waiting for Steam!".to_string())?
+        .map_err(|_| "Steam dropped the query!".to_string())?
+}
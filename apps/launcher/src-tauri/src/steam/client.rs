@@ -1,27 +1,233 @@
+//! The Steam thread. Steamworks wants its `SingleClient` pumped from a single thread, so
+//! one dedicated thread owns the `Client` and `SingleClient`, runs callbacks, and handles
+//! mount/unmount requests one at a time. Commands talk to the backend it publishes.
+
+use super::backend::SteamBackend;
+use super::backend::SteamworksBackend;
 use lazy_static::lazy_static;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use steamworks::Client;
 use steamworks::SingleClient;
+use tokio::sync::oneshot;
 use tokio::sync::Mutex;
 
+/// We mount as DayZ.
+pub const DAYZ_APP_ID: u32 = 221100;
+
+/// How often the Steam thread runs callbacks.
+const CALLBACK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the Steam thread can be asked to do. Requests are handled in the order they're sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SteamRequest {
+    Mount,
+    Unmount,
+    Remount,
+    /// Like `Remount`, but only if the API is supposed to be mounted. Never undoes an `Unmount`.
+    Reconnect,
+}
+
+/// Whether the API should be mounted, i.e. the last request was a mount and not an unmount.
+/// Only the Steam thread writes this.
+static MOUNT_WANTED: AtomicBool = AtomicBool::new(false);
+
+type Envelope = (SteamRequest, oneshot::Sender<Result<(), String>>);
+
 lazy_static! {
-    pub static ref STEAM_CLIENT: Arc<Mutex<Option<Client>>> = Arc::new(Mutex::new(None));
+    /// What the commands talk to, set while the API is mounted.
+    pub static ref STEAM_BACKEND: Arc<Mutex<Option<Arc<dyn SteamBackend>>>> =
+        Arc::new(Mutex::new(None));
+    static ref STEAM_THREAD: std::sync::Mutex<Option<mpsc::Sender<Envelope>>> =
+        std::sync::Mutex::new(None);
 }
 
-// NOTE: Only one thread may have this at a time, so no mutex 😛
-pub static mut STEAM_SINGLE: Option<SingleClient> = None;
+pub async fn get_backend() -> Option<Arc<dyn SteamBackend>> {
+    let steam_backend_ref = STEAM_BACKEND.clone();
+    let backend = steam_backend_ref.lock().await.clone();
+    backend
+}
+
+/// Whether the API is supposed to be mounted, even if mounting it failed.
+pub fn is_mount_wanted() -> bool {
+    MOUNT_WANTED.load(Ordering::SeqCst)
+}
 
-pub async fn get_client() -> Option<Client> {
-    let steam_client_ref = STEAM_CLIENT.clone();
-    let client = steam_client_ref.lock().await.to_owned();
-    client
+/// Starts the Steam thread, if it isn't running already.
+pub fn start() {
+    let mut steam_thread = STEAM_THREAD.lock().unwrap();
+    steam_thread.get_or_insert_with(spawn_thread);
 }
 
-pub fn get_single() -> &'static SingleClient {
-    unsafe {
-        match &STEAM_SINGLE {
-            Some(single) => single,
-            None => panic!("Steam single not initialized"),
+/// Sends a request to the Steam thread (starting it if needed) and waits until it's handled.
+pub async fn request(request: SteamRequest) -> Result<(), String> {
+    let (reply, response) = oneshot::channel();
+
+    // Don't hold the lock across the await
+    {
+        let mut steam_thread = STEAM_THREAD.lock().unwrap();
+        let sender = steam_thread.get_or_insert_with(spawn_thread);
+        if sender.send((request, reply)).is_err() {
+            return Err("The Steam thread has stopped!".to_string());
+        }
+    }
+
+    response
+        .await
+        .map_err(|_| "The Steam thread has stopped!".to_string())?
+}
+
+fn spawn_thread() -> mpsc::Sender<Envelope> {
+    let (sender, receiver) = mpsc::channel::<Envelope>();
+
+    thread::Builder::new()
+        .name("steam".to_string())
+        .spawn(move || SteamThread::default().run(receiver))
+        .expect("Failed to start the Steam thread");
+
+    sender
+}
+
+/// Everything owned by the Steam thread. Never leaves it.
+#[derive(Default)]
+struct SteamThread {
+    client: Option<Client>,
+    single: Option<SingleClient>,
+    backend: Option<Arc<dyn SteamBackend>>,
+}
+
+impl SteamThread {
+    fn run(mut self, receiver: mpsc::Receiver<Envelope>) {
+        loop {
+            match receiver.recv_timeout(CALLBACK_INTERVAL) {
+                Ok((request, reply)) => {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| self.handle(request)))
+                        .unwrap_or_else(|_| Err(format!("Steam panicked handling {:?}!", request)));
+                    // Nobody waiting for the answer is fine
+                    let _ = reply.send(result);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            // Every callback runs on this thread, one panicking shouldn't take Steam down
+            // for the rest of the session
+            if panic::catch_unwind(AssertUnwindSafe(|| self.run_callbacks())).is_err() {
+                println!("steam_thread: A Steam callback panicked, carrying on");
+            }
+        }
+    }
+
+    fn handle(&mut self, request: SteamRequest) -> Result<(), String> {
+        match request {
+            SteamRequest::Mount => {
+                MOUNT_WANTED.store(true, Ordering::SeqCst);
+                self.mount()
+            }
+            SteamRequest::Unmount => {
+                MOUNT_WANTED.store(false, Ordering::SeqCst);
+                self.unmount()
+            }
+            SteamRequest::Remount => {
+                MOUNT_WANTED.store(true, Ordering::SeqCst);
+                self.unmount()?;
+                self.mount()
+            }
+            SteamRequest::Reconnect if is_mount_wanted() => {
+                self.unmount()?;
+                self.mount()
+            }
+            SteamRequest::Reconnect => Ok(()),
+        }
+    }
+
+    fn mount(&mut self) -> Result<(), String> {
+        // Already mounted
+        if self.backend.is_some() {
+            return Ok(());
+        }
+
+        // No Steam needed, mount the fake instead
+        if let Some(fake) = fake_backend() {
+            self.publish(Some(fake));
+            return Ok(());
         }
+
+        // Mount API with DayZ app id
+        let (client, single) = Client::init_app(DAYZ_APP_ID).map_err(|e| {
+            println!("steam_thread: Error initializing Steamworks: {}", e);
+            e.to_string()
+        })?;
+
+        self.publish(Some(Arc::new(SteamworksBackend::new(client.clone()))));
+        self.client = Some(client);
+        self.single = Some(single);
+
+        println!("steam_thread: Steamworks API mounted successfully!");
+        Ok(())
     }
+
+    fn unmount(&mut self) -> Result<(), String> {
+        // Commands should stop talking to Steam right away
+        self.publish(None);
+
+        // We don't have a client to unmount (or it was the fake)
+        let Some(single) = self.single.take() else {
+            return Ok(());
+        };
+
+        // Run some last callbacks!
+        single.run_callbacks();
+        self.client = None;
+        drop(single);
+
+        // Now we nuke the API 💣
+        println!("steam_thread: Shutting down Steamworks API");
+        Client::shutdown();
+
+        // Now we have to quickly remount the api without an app id (0) and unmount it again,
+        // so Steam stops showing us as in DayZ and we don't get any errors
+        if let Err(e) = Client::init_app(0) {
+            println!("steam_thread: Error initializing Steamworks: {}", e);
+            return Err(e.to_string());
+        }
+
+        // Naughty! 🤭
+        Client::shutdown();
+
+        Ok(())
+    }
+
+    fn run_callbacks(&self) {
+        if let Some(single) = &self.single {
+            single.run_callbacks();
+        }
+
+        // Backends with their own callbacks (the fake) get pumped here too
+        if let Some(backend) = &self.backend {
+            backend.run_callbacks();
+        }
+    }
+
+    fn publish(&mut self, backend: Option<Arc<dyn SteamBackend>>) {
+        self.backend = backend.clone();
+        *STEAM_BACKEND.blocking_lock() = backend;
+    }
+}
+
+/// The in-memory Steam, when built with `fake-steam`.
+#[cfg(feature = "fake-steam")]
+fn fake_backend() -> Option<Arc<dyn SteamBackend>> {
+    Some(Arc::new(super::fake::FakeSteam::dayz()))
+}
+
+#[cfg(not(feature = "fake-steam"))]
+fn fake_backend() -> Option<Arc<dyn SteamBackend>> {
+    None
 }
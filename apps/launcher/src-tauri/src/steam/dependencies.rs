@@ -0,0 +1,153 @@
+//! Workshop dependency resolution. DayZ mods list the mods they need (CF, Dabs Framework...)
+//! as UGC children, so queueing a mod walks those children and queues what's missing first.
+
+use super::backend::fetch_items;
+use super::backend::SteamBackend;
+use super::backend::WorkshopItem;
+use super::backend::QUERY_PAGE_SIZE;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Upper bound on how many items one resolution may pull in, in case something is very wrong.
+const MAX_ITEMS: usize = 256;
+
+/// Everything a set of mods needs.
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// Every item, dependencies before the items that need them.
+    pub order: Vec<u64>,
+    pub items: HashMap<u64, WorkshopItem>,
+    /// Items that end up requiring themselves. They're still in `order`, the cycle is
+    /// just broken wherever we ran into it.
+    pub cycles: Vec<Vec<u64>>,
+}
+
+impl Resolution {
+    /// A resolution of just the given item, for when we can't ask Steam.
+    pub fn single(published_file_id: u64) -> Self {
+        Resolution {
+            order: vec![published_file_id],
+            ..Default::default()
+        }
+    }
+
+    pub fn in_cycle(&self, published_file_id: u64) -> bool {
+        self.cycles
+            .iter()
+            .any(|cycle| cycle.contains(&published_file_id))
+    }
+}
+
+/// A mod in a resolved set, as shown to the user before (or while) it downloads.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, specta::Type)]
+pub struct ResolvedMod {
+    pub published_file_id: String,
+    pub title: String,
+    /// Direct dependencies.
+    pub dependencies: Vec<String>,
+    pub installed: bool,
+    /// In the download queue, either just now or from before.
+    pub queued: bool,
+    pub in_cycle: bool,
+}
+
+/// Resolves `roots` and everything they require, level by level.
+pub async fn resolve(backend: &dyn SteamBackend, roots: &[u64]) -> Result<Resolution, String> {
+    let mut items: HashMap<u64, WorkshopItem> = HashMap::new();
+    let mut frontier = dedupe(roots.iter().copied());
+
+    while !frontier.is_empty() {
+        if items.len() + frontier.len() > MAX_ITEMS {
+            return Err(format!(
+                "Too many dependencies, stopped after {} items!",
+                items.len()
+            ));
+        }
+
+        for page in frontier.chunks(QUERY_PAGE_SIZE) {
+            for item in fetch_items(backend, page.to_vec()).await? {
+                items.insert(item.published_file_id, item);
+            }
+        }
+
+        // Steam doesn't return deleted or hidden items, they still get an entry so we
+        // don't ask again and they show up in the result
+        for id in &frontier {
+            items.entry(*id).or_insert_with(|| WorkshopItem {
+                published_file_id: *id,
+                ..Default::default()
+            });
+        }
+
+        let children = frontier
+            .iter()
+            .flat_map(|id| items[id].children.clone())
+            .filter(|child| !items.contains_key(child));
+        frontier = dedupe(children);
+    }
+
+    let (order, cycles) = order(&items, roots);
+    Ok(Resolution {
+        order,
+        items,
+        cycles,
+    })
+}
+
+/// Orders items so dependencies come first (depth first, post-order), and reports cycles.
+pub fn order(items: &HashMap<u64, WorkshopItem>, roots: &[u64]) -> (Vec<u64>, Vec<Vec<u64>>) {
+    let mut walk = Walk {
+        items,
+        done: HashSet::new(),
+        path: Vec::new(),
+        order: Vec::new(),
+        cycles: Vec::new(),
+    };
+
+    for root in roots {
+        walk.visit(*root);
+    }
+
+    (walk.order, walk.cycles)
+}
+
+struct Walk<'a> {
+    items: &'a HashMap<u64, WorkshopItem>,
+    done: HashSet<u64>,
+    /// Items we're in the middle of, a child on here means a cycle.
+    path: Vec<u64>,
+    order: Vec<u64>,
+    cycles: Vec<Vec<u64>>,
+}
+
+impl Walk<'_> {
+    fn visit(&mut self, id: u64) {
+        if self.done.contains(&id) {
+            return;
+        }
+        if let Some(start) = self.path.iter().position(|visiting| *visiting == id) {
+            self.cycles.push(self.path[start..].to_vec());
+            return;
+        }
+
+        self.path.push(id);
+        let children = self
+            .items
+            .get(&id)
+            .map(|item| item.children.clone())
+            .unwrap_or_default();
+        for child in children {
+            self.visit(child);
+        }
+        self.path.pop();
+
+        self.done.insert(id);
+        self.order.push(id);
+    }
+}
+
+/// Keeps the first of every id, in order.
+fn dedupe(ids: impl Iterator<Item = u64>) -> Vec<u64> {
+    let mut seen = HashSet::new();
+    ids.filter(|id| seen.insert(*id)).collect()
+}
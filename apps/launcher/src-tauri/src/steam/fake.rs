@@ -0,0 +1,487 @@
+//! Scriptable in-memory Steam, enabled with the `fake-steam` feature and always built for tests.
+//! Simulates subscriptions, download progress, install folders and failures, so the
+//! mod queue and launch flows can be driven without Steam running.
+//!
+//! Like the real thing, nothing happens right away. Subscribe/unsubscribe callbacks and
+//! download progress only move when `run_callbacks` (or `tick`) is called.
+
+// The app only mounts `dayz()`, the rest is for scripting tests and not all of it is used yet
+#![allow(dead_code)]
+
+use super::backend::DownloadEvent;
+use super::backend::DownloadListener;
+use super::backend::InstallInfo;
+use super::backend::QueryCallback;
+use super::backend::SearchCallback;
+use super::backend::SourcePlayer;
+use super::backend::SteamBackend;
+use super::backend::SteamCallback;
+use super::backend::WorkshopItem;
+use super::backend::WorkshopPage;
+use super::backend::WorkshopSearch;
+use super::backend::WorkshopSort;
+use super::backend::QUERY_PAGE_SIZE;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// How much of a download finishes every tick, unless the item says otherwise.
+const DEFAULT_BYTES_PER_TICK: u64 = 1024 * 1024;
+
+/// A workshop item the fake knows about.
+#[derive(Debug, Clone)]
+pub struct FakeItem {
+    pub info: WorkshopItem,
+    pub total_bytes: u64,
+    pub bytes_per_tick: u64,
+    /// Fail the download with this reason once some of it is downloaded.
+    pub fail_download: Option<String>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct FakeDownload {
+    downloaded: u64,
+    total: u64,
+    bytes_per_tick: u64,
+}
+
+#[derive(Default)]
+struct FakeState {
+    /// Installed apps and their install dirs.
+    apps: HashMap<u32, String>,
+    items: HashMap<u64, FakeItem>,
+    subscribed: Vec<u64>,
+    downloads: HashMap<u64, FakeDownload>,
+    installed: HashMap<u64, InstallInfo>,
+    /// Installed items with a newer version on the workshop.
+    needs_update: HashSet<u64>,
+    /// Scripted failures for the next subscribe of an item.
+    subscribe_failures: HashMap<u64, String>,
+    /// Download failures that happened, so tests and the UI can see them.
+    failed_downloads: HashMap<u64, String>,
+    pending: Vec<Box<dyn FnOnce() + Send>>,
+    listeners: Vec<DownloadListener>,
+    user_name: String,
+    user_steam_id: u64,
+    players: HashMap<u64, Vec<SourcePlayer>>,
+    /// Flip off to act like Steam exited.
+    offline: bool,
+    now: u32,
+}
+
+pub struct FakeSteam {
+    state: Mutex<FakeState>,
+    /// Install folders are created under here, so removing a mod works like it does for real.
+    install_root: PathBuf,
+}
+
+impl FakeSteam {
+    pub fn new() -> FakeSteam {
+        FakeSteam {
+            state: Mutex::new(FakeState {
+                user_name: "Survivor".to_string(),
+                user_steam_id: 76561197960287930,
+                now: 1_700_000_000,
+                ..Default::default()
+            }),
+            install_root: std::env::temp_dir().join("ftll-fake-steam"),
+        }
+    }
+
+    /// A fake with DayZ installed, what the app mounts when built with `fake-steam`.
+    pub fn dayz() -> FakeSteam {
+        let fake = FakeSteam::new();
+        let dayz_dir = fake.install_root.join("DayZ");
+        fake.add_app(221100, &dayz_dir.to_string_lossy());
+        fake
+    }
+
+    pub fn add_app(&self, app_id: u32, install_dir: &str) {
+        self.state().apps.insert(app_id, install_dir.to_string());
+    }
+
+    /// Makes a workshop item available to subscribe to.
+    pub fn add_item(&self, item: FakeItem) {
+        self.state().items.insert(item.info.published_file_id, item);
+    }
+
+    /// Shorthand for an item with just an id, title and size.
+    pub fn add_simple_item(&self, published_file_id: u64, title: &str, total_bytes: u64) {
+        let now = self.state().now;
+        self.add_item(FakeItem {
+            info: WorkshopItem {
+                published_file_id,
+                title: title.to_string(),
+                time_created: now,
+                time_updated: now,
+                file_size: total_bytes.min(u32::MAX as u64) as u32,
+                accepted_for_use: true,
+                ..Default::default()
+            },
+            total_bytes,
+            bytes_per_tick: DEFAULT_BYTES_PER_TICK,
+            fail_download: None,
+        });
+    }
+
+    /// Makes `published_file_id` require `dependency`.
+    pub fn add_dependency(&self, published_file_id: u64, dependency: u64) {
+        let mut state = self.state();
+        if let Some(item) = state.items.get_mut(&published_file_id) {
+            item.info.children.push(dependency);
+            item.info.num_children = item.info.children.len() as u32;
+        }
+    }
+
+    /// Makes the next subscribe to this item fail with `reason`.
+    pub fn fail_next_subscribe(&self, published_file_id: u64, reason: &str) {
+        self.state()
+            .subscribe_failures
+            .insert(published_file_id, reason.to_string());
+    }
+
+    /// Installs an item immediately, as if it had been downloaded earlier.
+    pub fn install(&self, published_file_id: u64) {
+        let mut state = self.state();
+        if !state.subscribed.contains(&published_file_id) {
+            state.subscribed.push(published_file_id);
+        }
+        let total = state
+            .items
+            .get(&published_file_id)
+            .map(|item| item.total_bytes)
+            .unwrap_or(0);
+        self.finish_install(&mut state, published_file_id, total);
+    }
+
+    /// Publishes a new version of an item, so an installed copy is out of date.
+    pub fn publish_update(&self, published_file_id: u64) {
+        let mut state = self.state();
+        state.now += 1;
+        let now = state.now;
+        let Some(item) = state.items.get_mut(&published_file_id) else {
+            return;
+        };
+        item.info.time_updated = now;
+
+        if state.installed.contains_key(&published_file_id) {
+            state.needs_update.insert(published_file_id);
+        }
+    }
+
+    /// Sets the players Steam reports for a game server.
+    pub fn set_players(&self, server_steam_id: u64, players: Vec<SourcePlayer>) {
+        self.state().players.insert(server_steam_id, players);
+    }
+
+    /// Pretends Steam exited (or came back).
+    pub fn set_offline(&self, offline: bool) {
+        self.state().offline = offline;
+    }
+
+    /// Why an item's download failed, if it did.
+    pub fn download_failure(&self, published_file_id: u64) -> Option<String> {
+        self.state()
+            .failed_downloads
+            .get(&published_file_id)
+            .cloned()
+    }
+
+    /// Advances every download by one step and runs queued callbacks.
+    pub fn tick(&self) {
+        let callbacks = {
+            let mut state = self.state();
+            state.now += 1;
+
+            let downloading: Vec<u64> = state.downloads.keys().copied().collect();
+            for id in downloading {
+                // e.g. the disk fills up, which only happens once something is written
+                let started = state.downloads[&id].downloaded > 0;
+                let failure = state
+                    .items
+                    .get(&id)
+                    .and_then(|item| item.fail_download.clone())
+                    .filter(|_| started);
+                if let Some(reason) = failure {
+                    state.downloads.remove(&id);
+                    state.failed_downloads.insert(id, reason.clone());
+                    Self::notify(
+                        &mut state,
+                        DownloadEvent::Downloaded {
+                            published_file_id: id,
+                            result: Err(reason),
+                        },
+                    );
+                    continue;
+                }
+
+                let download = state.downloads.get_mut(&id).unwrap();
+                download.downloaded =
+                    (download.downloaded + download.bytes_per_tick).min(download.total);
+                if download.downloaded >= download.total {
+                    let total = download.total;
+                    state.downloads.remove(&id);
+                    self.finish_install(&mut state, id, total);
+                    Self::notify(
+                        &mut state,
+                        DownloadEvent::Downloaded {
+                            published_file_id: id,
+                            result: Ok(()),
+                        },
+                    );
+                }
+            }
+
+            std::mem::take(&mut state.pending)
+        };
+
+        // Outside the lock, callbacks are allowed to call back into the fake
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    /// Queues `event` for every listener, they get it on the next tick like real callbacks.
+    fn notify(state: &mut FakeState, event: DownloadEvent) {
+        for listener in state.listeners.clone() {
+            let event = event.clone();
+            state.pending.push(Box::new(move || listener(event)));
+        }
+    }
+
+    fn start_download(&self, state: &mut FakeState, published_file_id: u64) {
+        let Some(item) = state.items.get(&published_file_id) else {
+            return;
+        };
+
+        let download = FakeDownload {
+            downloaded: 0,
+            total: item.total_bytes,
+            bytes_per_tick: item.bytes_per_tick.max(1),
+        };
+        state.failed_downloads.remove(&published_file_id);
+        state.downloads.insert(published_file_id, download);
+    }
+
+    fn finish_install(&self, state: &mut FakeState, published_file_id: u64, total: u64) {
+        let folder = self.install_root.join(published_file_id.to_string());
+        if let Err(e) = fs::create_dir_all(&folder) {
+            println!("fake_steam: Failed to create install folder: {}", e);
+        }
+
+        // Steam stamps an install with the version's update time, not when it was installed
+        let timestamp = state
+            .items
+            .get(&published_file_id)
+            .map(|item| item.info.time_updated)
+            .unwrap_or(state.now);
+        state.needs_update.remove(&published_file_id);
+        state.installed.insert(
+            published_file_id,
+            InstallInfo {
+                folder: folder.to_string_lossy().to_string(),
+                size_on_disk: total,
+                timestamp,
+            },
+        );
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Default for FakeSteam {
+    fn default() -> Self {
+        FakeSteam::new()
+    }
+}
+
+impl SteamBackend for FakeSteam {
+    fn is_app_installed(&self, app_id: u32) -> bool {
+        self.state().apps.contains_key(&app_id)
+    }
+
+    fn app_install_dir(&self, app_id: u32) -> String {
+        self.state().apps.get(&app_id).cloned().unwrap_or_default()
+    }
+
+    fn subscribe_item(&self, published_file_id: u64, callback: SteamCallback) {
+        let mut state = self.state();
+
+        let result = match state.subscribe_failures.remove(&published_file_id) {
+            Some(reason) => Err(reason),
+            None if !state.items.contains_key(&published_file_id) => {
+                Err("File not found".to_string())
+            }
+            None => {
+                if !state.subscribed.contains(&published_file_id) {
+                    state.subscribed.push(published_file_id);
+                }
+                if !state.installed.contains_key(&published_file_id) {
+                    self.start_download(&mut state, published_file_id);
+                }
+                Ok(())
+            }
+        };
+
+        state.pending.push(Box::new(move || callback(result)));
+    }
+
+    fn unsubscribe_item(&self, published_file_id: u64, callback: SteamCallback) {
+        let mut state = self.state();
+        state.subscribed.retain(|id| *id != published_file_id);
+        state.downloads.remove(&published_file_id);
+        state.pending.push(Box::new(move || callback(Ok(()))));
+    }
+
+    fn delete_item(&self, published_file_id: u64, callback: SteamCallback) {
+        let mut state = self.state();
+        state.items.remove(&published_file_id);
+        state.pending.push(Box::new(move || callback(Ok(()))));
+    }
+
+    fn download_item(&self, published_file_id: u64, _high_priority: bool) -> bool {
+        let mut state = self.state();
+        if !state.items.contains_key(&published_file_id) {
+            return false;
+        }
+
+        // Already on it
+        if state.downloads.contains_key(&published_file_id) {
+            return true;
+        }
+
+        // Up to date, Steam just says it's done
+        if state.installed.contains_key(&published_file_id)
+            && !state.needs_update.contains(&published_file_id)
+        {
+            Self::notify(
+                &mut state,
+                DownloadEvent::Downloaded {
+                    published_file_id,
+                    result: Ok(()),
+                },
+            );
+            return true;
+        }
+
+        state.installed.remove(&published_file_id);
+        self.start_download(&mut state, published_file_id);
+        true
+    }
+
+    fn item_install_info(&self, published_file_id: u64) -> Option<InstallInfo> {
+        self.state().installed.get(&published_file_id).cloned()
+    }
+
+    fn item_needs_update(&self, published_file_id: u64) -> bool {
+        self.state().needs_update.contains(&published_file_id)
+    }
+
+    fn item_download_info(&self, published_file_id: u64) -> Option<(u64, u64)> {
+        self.state()
+            .downloads
+            .get(&published_file_id)
+            .map(|download| (download.downloaded, download.total))
+    }
+
+    fn subscribed_items(&self) -> Vec<u64> {
+        self.state().subscribed.clone()
+    }
+
+    fn query_items(
+        &self,
+        published_file_ids: Vec<u64>,
+        callback: QueryCallback,
+    ) -> Result<(), String> {
+        let mut state = self.state();
+        let items: Vec<WorkshopItem> = published_file_ids
+            .iter()
+            .filter_map(|id| state.items.get(id))
+            .map(|item| item.info.clone())
+            .collect();
+
+        state.pending.push(Box::new(move || callback(Ok(items))));
+        Ok(())
+    }
+
+    fn search_workshop(
+        &self,
+        search: WorkshopSearch,
+        callback: SearchCallback,
+    ) -> Result<(), String> {
+        let mut state = self.state();
+        let text = search.text.map(|text| text.to_lowercase());
+        let mut items: Vec<WorkshopItem> = state
+            .items
+            .values()
+            .map(|item| item.info.clone())
+            .filter(|item| {
+                text.as_ref().map_or(true, |text| {
+                    item.title.to_lowercase().contains(text)
+                        || item.description.to_lowercase().contains(text)
+                })
+            })
+            .filter(|item| search.tags.iter().all(|tag| item.tags.contains(tag)))
+            .collect();
+
+        // Ties go to the lowest id so pages are stable
+        items.sort_by_key(|item| item.published_file_id);
+        match search.sort {
+            WorkshopSort::Trending => items.sort_by(|a, b| b.score.total_cmp(&a.score)),
+            WorkshopSort::MostSubscribed => items.sort_by_key(|item| Reverse(item.subscriptions)),
+            WorkshopSort::RecentlyUpdated => items.sort_by_key(|item| Reverse(item.time_updated)),
+        }
+
+        let total_results = items.len() as u32;
+        let items = items
+            .into_iter()
+            .skip(search.page.saturating_sub(1) as usize * QUERY_PAGE_SIZE)
+            .take(QUERY_PAGE_SIZE)
+            .collect();
+
+        state.pending.push(Box::new(move || {
+            callback(Ok(WorkshopPage {
+                items,
+                total_results,
+            }))
+        }));
+        Ok(())
+    }
+
+    fn watch_downloads(&self, listener: DownloadListener) {
+        self.state().listeners.push(listener);
+    }
+
+    fn user_name(&self) -> String {
+        self.state().user_name.clone()
+    }
+
+    fn user_steam_id(&self) -> u64 {
+        self.state().user_steam_id
+    }
+
+    fn user_avatar(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn players_from_source(&self, server_steam_id: u64) -> Vec<SourcePlayer> {
+        self.state()
+            .players
+            .get(&server_steam_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn is_alive(&self) -> bool {
+        !self.state().offline
+    }
+
+    fn run_callbacks(&self) {
+        self.tick();
+    }
+}
@@ -0,0 +1,238 @@
+//! The installed mod list, backed by a cache in the app cache dir. Workshop details are only
+//! re-queried when the installed version is newer than what we cached (or the details got
+//! old), folder sizes only when the install changed, and the list still works without Steam.
+
+use super::backend::fetch_items;
+use super::backend::InstallInfo;
+use super::backend::SteamBackend;
+use super::backend::WorkshopItem;
+use super::backend::QUERY_PAGE_SIZE;
+use super::ModInfoFoundEvent;
+use crate::query::now_secs;
+use anyhow::Result;
+use fs_extra::dir::get_size;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+use tokio::task;
+
+/// How long cached workshop details are used before asking Steam again, so titles and
+/// votes don't go stale forever.
+const DETAILS_MAX_AGE_SECS: i64 = 60 * 60;
+
+/// Emitted once `steam_get_installed_mods` is done, after every `ModInfoFoundEvent`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
+pub struct InstalledModsListedEvent {
+    count: u32,
+    /// Whether the list came from the cache because Steam isn't there.
+    offline: bool,
+}
+
+impl InstalledModsListedEvent {
+    pub fn new(count: usize, offline: bool) -> Self {
+        InstalledModsListedEvent {
+            count: count as u32,
+            offline,
+        }
+    }
+}
+
+/// What we know about an installed mod.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMod {
+    pub info: ModInfoFoundEvent,
+    /// `time_updated` of the workshop version `info` describes, 0 if we never got details.
+    pub time_updated: u32,
+    /// Unix timestamp (seconds) of when `info` was queried, 0 if we never got details.
+    pub fetched_at: i64,
+    pub folder: String,
+    /// Install timestamp `size` was worked out for.
+    pub installed_at: u32,
+    pub size: u64,
+}
+
+impl CachedMod {
+    /// Whether the details still describe the installed version.
+    fn is_fresh(&self, install: &InstallInfo, now: i64) -> bool {
+        self.fetched_at > 0
+            && self.time_updated >= install.timestamp
+            && now - self.fetched_at < DETAILS_MAX_AGE_SECS
+    }
+
+    fn mod_info(&self) -> ModInfoFoundEvent {
+        let mut info = self.info.clone();
+        info.file_size = self.size as f64;
+        info
+    }
+}
+
+/// Installed mods by published file id.
+pub type ModCache = HashMap<u64, CachedMod>;
+
+/// Brings the cache in line with what Steam has installed and returns the mod list.
+/// Returns whether the cache changed, so the caller knows to save it.
+pub async fn refresh(
+    backend: &dyn SteamBackend,
+    cache: &mut ModCache,
+) -> (Vec<ModInfoFoundEvent>, bool) {
+    let now = now_secs();
+    let installed: Vec<(u64, InstallInfo)> = backend
+        .subscribed_items()
+        .into_iter()
+        .filter_map(|id| backend.item_install_info(id).map(|info| (id, info)))
+        .collect();
+
+    // Forget whatever isn't installed anymore
+    let before = cache.len();
+    cache.retain(|id, _| installed.iter().any(|(installed_id, _)| installed_id == id));
+    let mut changed = cache.len() != before;
+
+    // Only ask the workshop about items we have no (current) details for
+    let stale: Vec<u64> = installed
+        .iter()
+        .filter(|(id, install)| {
+            !cache
+                .get(id)
+                .is_some_and(|cached| cached.is_fresh(install, now))
+        })
+        .map(|(id, _)| *id)
+        .collect();
+    for page in stale.chunks(QUERY_PAGE_SIZE) {
+        let items = match fetch_items(backend, page.to_vec()).await {
+            Ok(items) => items,
+            Err(e) => {
+                // Keep what we had, we'll try again next time
+                println!("installed_mods: Couldn't query the workshop: {}", e);
+                break;
+            }
+        };
+
+        for item in items {
+            let Some(cached) = cache.get_mut(&item.published_file_id) else {
+                cache.insert(item.published_file_id, new_entry(item, now));
+                changed = true;
+                continue;
+            };
+            cached.time_updated = item.time_updated;
+            cached.fetched_at = now;
+            cached.info = ModInfoFoundEvent::from(item);
+            changed = true;
+        }
+    }
+
+    // Walking folders is slow, only do it when the install changed
+    let changed_folders: Vec<(u64, String)> = installed
+        .iter()
+        .filter(|(id, install)| {
+            !cache.get(id).is_some_and(|cached| {
+                cached.folder == install.folder && cached.installed_at == install.timestamp
+            })
+        })
+        .map(|(id, install)| (*id, install.folder.clone()))
+        .collect();
+    let mut sizes = folder_sizes(changed_folders).await;
+
+    let mut mods = Vec::new();
+    for (id, install) in installed {
+        let cached = cache.entry(id).or_insert_with(|| {
+            // Steam didn't tell us anything about it, list it without details
+            changed = true;
+            new_entry(
+                WorkshopItem {
+                    published_file_id: id,
+                    ..Default::default()
+                },
+                0,
+            )
+        });
+
+        match sizes.remove(&id) {
+            Some(Ok(size)) => {
+                cached.size = size;
+                cached.folder = install.folder;
+                cached.installed_at = install.timestamp;
+                changed = true;
+            }
+            Some(Err(e)) => println!("installed_mods: Couldn't size {}: {}", id, e),
+            None => {}
+        }
+
+        mods.push(cached.mod_info());
+    }
+
+    (mods, changed)
+}
+
+/// The mod list as of the last refresh, for when Steam isn't there.
+/// Mods whose folder is gone have been removed since and are left out.
+pub fn cached(cache: &ModCache) -> Vec<ModInfoFoundEvent> {
+    cache
+        .values()
+        .filter(|cached| Path::new(&cached.folder).exists())
+        .map(CachedMod::mod_info)
+        .collect()
+}
+
+/// Sizes every folder on the blocking pool, walking big mods would stall the runtime.
+async fn folder_sizes(folders: Vec<(u64, String)>) -> HashMap<u64, Result<u64, String>> {
+    if folders.is_empty() {
+        return HashMap::new();
+    }
+
+    let sizes = task::spawn_blocking(move || {
+        folders
+            .into_iter()
+            .map(|(id, folder)| (id, get_size(folder).map_err(|e| e.to_string())))
+            .collect()
+    })
+    .await;
+
+    sizes.unwrap_or_else(|e| {
+        println!("installed_mods: Sizing folders failed: {}", e);
+        HashMap::new()
+    })
+}
+
+fn new_entry(item: WorkshopItem, fetched_at: i64) -> CachedMod {
+    CachedMod {
+        time_updated: item.time_updated,
+        fetched_at,
+        info: ModInfoFoundEvent::from(item),
+        folder: String::new(),
+        installed_at: 0,
+        size: 0,
+    }
+}
+
+fn cache_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(app_handle
+        .path()
+        .app_cache_dir()?
+        .join("installed_mods.json"))
+}
+
+/// Loads the cache, missing file means an empty one.
+pub fn load(app_handle: &AppHandle) -> Result<ModCache> {
+    let path = cache_path(app_handle)?;
+    if !path.exists() {
+        return Ok(ModCache::new());
+    }
+
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub fn save(app_handle: &AppHandle, cache: &ModCache) -> Result<()> {
+    let path = cache_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, serde_json::to_string(cache)?)?;
+    Ok(())
+}
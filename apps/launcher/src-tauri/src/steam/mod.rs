@@ -1,95 +1,314 @@
 use anyhow::Result;
-use fs_extra::dir::get_size;
+use backend::DownloadEvent;
+use backend::SteamBackend;
+use backend::WorkshopItem;
+use client::SteamRequest;
+use dependencies::Resolution;
+use dependencies::ResolvedMod;
+use installed::InstalledModsListedEvent;
 use lazy_static::lazy_static;
+use queue::DownloadRate;
+use queue::QueueEntry;
+use queue::QueueEntry32;
+use queue::QueueState;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::fs;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 use tauri::AppHandle;
 use tauri_specta::Event;
+use tokio::sync::mpsc;
+use tokio::sync::Notify;
 use tokio::sync::RwLock;
 use tokio::{task, time};
 
+pub mod backend;
 pub mod client;
+pub mod dependencies;
+#[cfg(any(test, feature = "fake-steam"))]
+pub mod fake;
+pub mod installed;
+pub mod queue;
+pub mod supervisor;
+pub mod updates;
+pub mod workshop;
+
+/// How often the mod daemon checks on the active download's progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long Steam gets to answer a subscribe before the mod is marked as failed.
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(60);
 
 // NOTE: We are using mainly RwLocks here because we dont need to be able to
 // write to most of them, all of the time, We need to be able to read from them most of the time.
 lazy_static! {
-    static ref IS_CALLBACK_DAEMON_RUNNING: Arc<RwLock<bool>> = Arc::new(RwLock::new(false));
     static ref IS_MOD_DAEMON_RUNNING: Arc<RwLock<bool>> = Arc::new(RwLock::new(false));
-    static ref MOD_DOWNLOAD_QUEUE: Arc<RwLock<VecDeque<u64>>> =
+    static ref MOD_DOWNLOAD_QUEUE: Arc<RwLock<VecDeque<QueueEntry>>> =
         Arc::new(RwLock::new(VecDeque::new()));
+    /// Pokes the mod daemon when the queue changes, so it doesn't wait for the next poll.
+    static ref MDQ_WAKE: Arc<Notify> = Arc::new(Notify::new());
+    /// Loaded from disk the first time the installed mods are listed.
+    static ref INSTALLED_MOD_CACHE: Arc<RwLock<Option<installed::ModCache>>> =
+        Arc::new(RwLock::new(None));
 }
 
-/// Clears the mod download queue.
-/// TODO: don't clear currently downloading mod
+/// Clears the mod download queue. The active download is kept, cancel it to get rid of it.
 #[tauri::command]
 #[specta::specta]
-pub async fn mdq_clear() -> Result<(), String> {
+pub async fn mdq_clear(app_handle: AppHandle) -> Result<(), String> {
     let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
     let mut mod_queue = mod_queue_ref.write().await;
-    (*mod_queue).clear();
+    (*mod_queue).retain(|entry| entry.state.is_active());
+    queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
     Ok(())
 }
 
-/// Adds a mod to the download queue.
+/// Adds a mod to the download queue, optionally noting the server or preset it's for.
+/// Mods it requires are resolved through the workshop and queued ahead of it, skipping
+/// anything installed or queued already. Returns the whole resolved set, in download order,
+/// even when everything in it was installed or queued already.
 #[tauri::command]
 #[specta::specta]
-pub async fn mdq_add_mod(published_file_id: String) -> Result<(), String> {
+pub async fn mdq_add_mod(
+    published_file_id: String,
+    server: Option<String>,
+    preset: Option<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<ResolvedMod>, String> {
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
+
+    // Check that steam client!
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("No steam client found!".to_string());
+    }
+    let backend = backend.unwrap();
+
+    // Figure out what it needs, we can still queue the mod itself if Steam won't tell us
+    let resolution = match dependencies::resolve(backend.as_ref(), &[published_file_id]).await {
+        Ok(resolution) => resolution,
+        Err(e) => {
+            println!(
+                "mdq_add_mod: Couldn't resolve dependencies of {}: {}",
+                published_file_id, e
+            );
+            Resolution::single(published_file_id)
+        }
+    };
+    for cycle in &resolution.cycles {
+        println!("mdq_add_mod: Dependency cycle: {:?}", cycle);
+    }
+
+    // Queue whatever is missing, dependencies first
+    // Nothing missing isn't an error, the frontend can call this rapidly (user spams a
+    // button) and the flags keep the UI in sync either way
     let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
     let mut mod_queue = mod_queue_ref.write().await;
+    let mut resolved: Vec<ResolvedMod> = Vec::new();
+    let mut added = 0;
+    for id in &resolution.order {
+        let item = resolution.items.get(id);
+        let installed = backend.item_install_info(*id).is_some();
+        let mut queued = (*mod_queue)
+            .iter()
+            .any(|entry| entry.published_file_id == *id);
+
+        if !installed && !queued {
+            (*mod_queue).push_back(QueueEntry::new(*id, server.clone(), preset.clone()));
+            queued = true;
+            added += 1;
+        }
 
-    // Check if the mod is already in the queue
-    // We do not return an error here as the frontend can call this rapidly
-    // and we don't want the UI out of sync. (user spams a button)
-    if (*mod_queue).contains(&published_file_id) {
-        return Ok(());
+        resolved.push(ResolvedMod {
+            published_file_id: id.to_string(),
+            title: item.map(|item| item.title.clone()).unwrap_or_default(),
+            dependencies: item
+                .map(|item| {
+                    item.children
+                        .iter()
+                        .map(|child| child.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            installed,
+            queued,
+            in_cycle: resolution.in_cycle(*id),
+        });
     }
 
-    // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
-        return Err("No steam client found!".to_string());
+    if added > 0 {
+        queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
+        MDQ_WAKE.notify_one();
     }
-    let client = client.unwrap();
 
-    // Now check if the mod is already installed
-    let ugc = client.ugc();
-    let is_installed = ugc.item_install_info(steamworks::PublishedFileId(published_file_id));
+    Ok(resolved)
+}
 
-    match is_installed {
+/// Removes a mod from the download queue. Won't touch the active download, cancel it instead.
+#[tauri::command]
+#[specta::specta]
+pub async fn mdq_remove_mod(
+    published_file_id: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let published_file_id = published_file_id
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?;
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mut mod_queue = mod_queue_ref.write().await;
+
+    let entry = (*mod_queue)
+        .iter()
+        .find(|entry| entry.published_file_id == published_file_id);
+    match entry {
+        Some(entry) if entry.state.is_active() => {
+            Err("Mod is downloading, cancel it instead!".to_string())
+        }
         Some(_) => {
-            (*mod_queue).push_back(published_file_id);
-            return Err("Mod already installed!".to_string());
+            (*mod_queue).retain(|entry| entry.published_file_id != published_file_id);
+            queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
+            Ok(())
         }
         None => {
-            (*mod_queue).push_back(published_file_id);
+            println!("mdq_remove_mod: Mod not found in queue, theres nothing to remove!");
             Ok(())
         }
     }
 }
 
-/// Removes a mod from the download queue.
-/// TODO: don't clear currently downloading mod
+/// Cancels a mod, active or not, and removes it from the queue.
+/// An active download is unsubscribed so Steam stops downloading it.
 #[tauri::command]
 #[specta::specta]
-pub async fn mdq_remove_mod(published_file_id: String) -> Result<(), String> {
+pub async fn mdq_cancel_mod(
+    published_file_id: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
     let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
     let mut mod_queue = mod_queue_ref.write().await;
 
-    if (*mod_queue).contains(&published_file_id) {
-        (*mod_queue).retain(|&x| x != published_file_id);
-        Ok(())
-    } else {
-        println!("mdq_remove_mod: Mod not found in queue, theres nothing to remove!");
-        Ok(())
+    let index = (*mod_queue)
+        .iter()
+        .position(|entry| entry.published_file_id == published_file_id)
+        .ok_or("Mod not found in queue!".to_string())?;
+    let entry = (*mod_queue).remove(index).unwrap();
+    queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
+    MDQ_WAKE.notify_one();
+    drop(mod_queue);
+
+    if entry.state.is_active() {
+        let backend = client::get_backend().await;
+        if backend.is_none() {
+            return Err("No steam client found!".to_string());
+        }
+        let backend = backend.unwrap();
+
+        backend.unsubscribe_item(
+            published_file_id,
+            Box::new(move |i| match i {
+                Ok(_) => println!("mdq_cancel_mod: {} unsubscribed", published_file_id),
+                Err(e) => println!("Error unsubscribing mod: {}", e),
+            }),
+        );
+    }
+
+    Ok(())
+}
+
+/// Moves a queued mod to `index` in the queue, so it starts sooner (or later).
+/// The active download keeps going wherever it ends up.
+#[tauri::command]
+#[specta::specta]
+pub async fn mdq_move_mod(
+    published_file_id: String,
+    index: u32,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let published_file_id = published_file_id
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?;
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mut mod_queue = mod_queue_ref.write().await;
+
+    let from = (*mod_queue)
+        .iter()
+        .position(|entry| entry.published_file_id == published_file_id)
+        .ok_or("Mod not found in queue!".to_string())?;
+    let entry = (*mod_queue).remove(from).unwrap();
+    let to = (index as usize).min(mod_queue.len());
+    (*mod_queue).insert(to, entry);
+
+    queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
+    MDQ_WAKE.notify_one();
+    Ok(())
+}
+
+/// Pauses a mod, the daemon skips it until it's resumed.
+/// NOTE: Steam has no per-item pause, so a download that already started may still finish in the background.
+#[tauri::command]
+#[specta::specta]
+pub async fn mdq_pause_mod(published_file_id: String, app_handle: AppHandle) -> Result<(), String> {
+    set_queue_state(&app_handle, &published_file_id, |state| match state {
+        QueueState::Installed => None,
+        _ => Some(QueueState::Paused),
+    })
+    .await
+}
+
+/// Resumes a paused mod, or retries a failed one.
+#[tauri::command]
+#[specta::specta]
+pub async fn mdq_resume_mod(
+    published_file_id: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    set_queue_state(&app_handle, &published_file_id, |state| match state {
+        QueueState::Paused | QueueState::Failed(_) => Some(QueueState::Queued),
+        _ => None,
+    })
+    .await
+}
+
+/// Moves a queued mod to the state `next` picks, if it picks one, then saves and emits the change.
+async fn set_queue_state(
+    app_handle: &AppHandle,
+    published_file_id: &str,
+    next: impl FnOnce(&QueueState) -> Option<QueueState>,
+) -> Result<(), String> {
+    let published_file_id = published_file_id
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?;
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mut mod_queue = mod_queue_ref.write().await;
+
+    let entry = (*mod_queue)
+        .iter_mut()
+        .find(|entry| entry.published_file_id == published_file_id)
+        .ok_or("Mod not found in queue!".to_string())?;
+    let Some(state) = next(&entry.state) else {
+        return Ok(());
+    };
+    entry.state = state.clone();
+
+    queue::save(app_handle, &mod_queue).map_err(|e| e.to_string())?;
+    MDQ_WAKE.notify_one();
+    drop(mod_queue);
+
+    ModQueueStateEvent {
+        published_file_id: published_file_id.to_string(),
+        state,
     }
+    .emit(app_handle)
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
 }
 
 /// Returns the progress of an active mod download. Will error if there is no active download.
@@ -100,28 +319,27 @@ pub async fn mdq_get_active_download_progress() -> Result<[String; 2], String> {
     // Don't carry those locks across awaits 😎
     let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
     let mod_queue = mod_queue_ref.read().await;
-    let front = (*mod_queue).front();
-    let front = front.cloned();
+    let active = (*mod_queue).iter().find(|entry| entry.state.is_active());
+    let active = active.map(|entry| entry.published_file_id);
     drop(mod_queue);
 
-    if front.is_none() {
+    if active.is_none() {
         return Err("No active download!".to_string());
     }
 
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("Now steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Get the download progress
-    let ugc = client.ugc();
-    let download_progress = ugc
-        .item_download_info(steamworks::PublishedFileId(front.unwrap()))
+    let download_progress = backend
+        .item_download_info(active.unwrap())
         .ok_or("There was an error getting your download progress!".to_string())?;
 
-    // Return "front" download progress
+    // Return active download progress
     Ok([
         download_progress.0.to_string(),
         download_progress.1.to_string(),
@@ -134,18 +352,57 @@ pub async fn mdq_get_active_download_progress() -> Result<[String; 2], String> {
 pub async fn mdq_get_active_download_id() -> Result<String, String> {
     let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
     let mod_queue = mod_queue_ref.read().await;
-    let front = (*mod_queue).front();
+    let active = (*mod_queue).iter().find(|entry| entry.state.is_active());
 
-    match front {
-        Some(id) => Ok(id.to_string()),
+    match active {
+        Some(entry) => Ok(entry.published_file_id.to_string()),
         None => Err("No active download!".to_string()),
     }
 }
 
+/// Lists everything in the download queue, in the order it'll be downloaded.
+#[tauri::command]
+#[specta::specta]
+pub async fn mdq_list() -> Result<Vec<QueueEntry32>, String> {
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mod_queue = mod_queue_ref.read().await;
+
+    Ok(mod_queue.iter().cloned().map(QueueEntry32::from).collect())
+}
+
+/// Restores the download queue saved by the last session, ahead of anything queued since.
+/// The daemon picks it back up once Steam is mounted.
+pub async fn mdq_restore(app_handle: AppHandle) {
+    let mut restored = match queue::load(&app_handle) {
+        Ok(restored) => restored,
+        Err(e) => {
+            println!("mdq_restore: Failed to load the saved queue: {}", e);
+            return;
+        }
+    };
+
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mut mod_queue = mod_queue_ref.write().await;
+    for entry in mod_queue.drain(..) {
+        if !restored
+            .iter()
+            .any(|saved| saved.published_file_id == entry.published_file_id)
+        {
+            restored.push_back(entry);
+        }
+    }
+
+    println!("mdq_restore: Restored {} queued mod(s)", restored.len());
+    *mod_queue = restored;
+    MDQ_WAKE.notify_one();
+}
+
 /// Starts the mod download queue daemon. This daemon will check if there are any mods in the queue
-/// then download them. This daemon will run continuously until the app is closed. Handles,
-/// unmounting of the steam api, and checking if the mod is already installed.
-/// Emits a "mdq_active_download_info" event while a mod is downloading.
+/// then download them, one at a time. This daemon will run continuously until the app is closed.
+/// It's driven by Steam's subscribe, download and install callbacks, and only polls for progress.
+/// Handles unmounting of the steam api, and checking if the mod is already installed.
+/// Emits a `ModQueueStateEvent` on every state change and a `ModDownloadProgressEvent`
+/// (plus the older `ActiveDownloadProgressEvent`) while a mod is downloading.
 #[tauri::command]
 #[specta::specta]
 pub async fn mdq_start_daemon(app_handle: AppHandle) -> Result<(), String> {
@@ -159,102 +416,308 @@ pub async fn mdq_start_daemon(app_handle: AppHandle) -> Result<(), String> {
     *is_mod_daemon_running = true;
 
     // Mod Daemon 👹
-    // This task will run continuously and check if there are any mods in the download queue
-    // to download. If there are, it will download them and remove them from the queue.
-    // If there are no mods in the queue, it will sleep for a bit and check again.
+    // This task waits for Steam to tell us something, for the queue to change, or for the
+    // next progress poll. Then it updates the queue and lets the frontend know.
     task::spawn(async move {
         let handle = app_handle.clone();
+        let (sender, mut receiver) = mpsc::unbounded_channel::<DownloadEvent>();
+        let mut watched: Option<Arc<dyn SteamBackend>> = None;
+        let mut rates: HashMap<u64, DownloadRate> = HashMap::new();
 
         loop {
-            // How fast do we want to check the queue?
-            time::sleep(Duration::from_millis(150)).await;
-
-            // Now lets grab the front of that queue!
-            // Keep in mind, we are grabbing the front, so we eventually have to put it back.
-            // P.S. Sometimes the queue is empty!
-            let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
-            let mut mod_queue = mod_queue_ref.write().await;
-            let front = mod_queue.pop_front();
-            if front.is_none() {
-                continue; // No mods in the queue, lets check again!
-            }
-            let front = front.unwrap();
+            let event = tokio::select! {
+                event = receiver.recv() => event,
+                _ = MDQ_WAKE.notified() => None,
+                _ = time::sleep(PROGRESS_INTERVAL) => None,
+            };
 
             // Sometimes we can unmount steam while the daemon is running, so we
             // need to check if steamworks is still initialized! 🤭
-            let client = client::get_client().await;
-            if client.is_none() {
-                println!("mdq_daemon: Steamworks not initialized, trying again!");
-                continue;
-            }
-            let client = client.unwrap();
+            // Steam went away? the supervisor will let us know once it's back
+            let backend = match supervisor::is_paused().await {
+                true => None,
+                false => client::get_backend().await,
+            };
 
-            // Lets check if the mod is installed?
-            let ugc = client.ugc();
-            let is_installed = ugc.item_install_info(steamworks::PublishedFileId(front));
-            if is_installed.is_some() {
-                println!(
-                    "mdq_daemon: Mod has been installed: {}",
-                    is_installed.unwrap().folder
+            let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+            let mut mod_queue = mod_queue_ref.write().await;
+            let mut step = MdqStep::default();
+
+            if let Some(event) = event {
+                mdq_handle_event(
+                    backend.as_deref(),
+                    &mut mod_queue,
+                    &mut rates,
+                    event,
+                    &mut step,
                 );
+            }
 
-                // Emit an extra event to let the frontend know the mod is installed
-                // Just in case the frontend is waiting for the download to finish
-                ActiveDownloadProgressEvent {
-                    published_file_id: front.to_string(),
-                    bytes_downloaded: 0.to_string(),
-                    bytes_total: 0.to_string(),
-                    percentage_downloaded: 100.0.to_string(),
+            if let Some(backend) = backend {
+                // New (or remounted) client, callbacks from the old one are gone so anything
+                // in flight has to be looked at again
+                if !watched
+                    .as_ref()
+                    .is_some_and(|watched| Arc::ptr_eq(watched, &backend))
+                {
+                    let listener = sender.clone();
+                    backend.watch_downloads(Arc::new(move |event| {
+                        let _ = listener.send(event);
+                    }));
+                    mdq_requeue_active(&mut mod_queue, &mut step);
+                    watched = Some(backend.clone());
                 }
-                .emit(&handle)
-                .expect("Failed to emit event!");
 
-                continue;
+                let now = Instant::now();
+                mdq_expire_subscribe(&mut mod_queue, now, &mut step);
+                mdq_start_next(backend.as_ref(), &mut mod_queue, &sender, now, &mut step);
+                mdq_poll_progress(backend.as_ref(), &mut mod_queue, &mut rates, now, &mut step);
             }
 
-            // Its not installed, either we are downloading it or we need to download it!
-            // Lets check our last download id...
-            let download_info = ugc.item_download_info(steamworks::PublishedFileId(front));
-            if download_info.is_some() {
-                // Add it back and check again later
-                mod_queue.push_front(front);
+            // Something changed, keep the saved queue in sync
+            if !step.states.is_empty() {
+                if let Err(e) = queue::save(&handle, &mod_queue) {
+                    println!("mdq_daemon: Failed to save the queue: {}", e);
+                }
+            }
+            drop(mod_queue);
 
-                // Gather the download info and send it to the frontend
-                let bytes_downloaded = download_info.unwrap().0;
-                let bytes_total = download_info.unwrap().1;
-                let percentage_downloaded = bytes_downloaded as f64 / bytes_total as f64 * 100.0;
+            for state in step.states {
+                state.emit(&handle).expect("Failed to emit event!");
+            }
+            if let Some(progress) = step.progress {
+                ActiveDownloadProgressEvent::from(&progress)
+                    .emit(&handle)
+                    .expect("Failed to emit event!");
+                progress.emit(&handle).expect("Failed to emit event!");
+            }
+        }
+    });
 
-                println!(
-                    "mdq_daemon: {} is downloading... {:.1}% ({}/{})",
-                    front, percentage_downloaded, bytes_downloaded, bytes_total
-                );
+    Ok(())
+}
 
-                // Emit the download progress
-                ActiveDownloadProgressEvent {
-                    published_file_id: front.to_string(),
-                    bytes_downloaded: bytes_downloaded.to_string(),
-                    bytes_total: bytes_total.to_string(),
-                    percentage_downloaded: percentage_downloaded.to_string(),
-                }
-                .emit(&handle)
-                .expect("Failed to emit event!");
+/// What one pass of the mod daemon wants to tell the frontend.
+#[derive(Default, Debug)]
+pub struct MdqStep {
+    pub states: Vec<ModQueueStateEvent>,
+    pub progress: Option<ModDownloadProgressEvent>,
+}
 
-                continue;
+/// Applies something Steam told us to the queue. Installed items leave the queue,
+/// failures stay in it as `Failed` until they're retried or cancelled.
+pub fn mdq_handle_event(
+    backend: Option<&dyn SteamBackend>,
+    mod_queue: &mut VecDeque<QueueEntry>,
+    rates: &mut HashMap<u64, DownloadRate>,
+    event: DownloadEvent,
+    step: &mut MdqStep,
+) {
+    let (id, result) = match event {
+        DownloadEvent::Subscribed {
+            published_file_id,
+            result,
+        } => {
+            // Steam answered, so it's not stuck anymore
+            if let Some(entry) = mod_queue
+                .iter_mut()
+                .find(|entry| entry.published_file_id == published_file_id)
+            {
+                entry.subscribing_since = None;
             }
 
-            // At this point we know the mod is not installed and not downloading...
-            // Lets cook that shit up 🍳 P.S. don't care about the callback
-            ugc.subscribe_item(steamworks::PublishedFileId(front), |_i| {});
-            mod_queue.push_front(front);
-            println!("mdq_daemon: ✅ Downloading mod: {}", front);
+            // Subscribed, make sure Steam actually downloads it, e.g. if it's an update
+            if let (Ok(_), Some(backend)) = (&result, backend) {
+                backend.download_item(published_file_id, true);
+            }
+            if result.is_ok() {
+                return;
+            }
+            (published_file_id, result)
+        }
+        DownloadEvent::Downloaded {
+            published_file_id,
+            result,
+        } => (published_file_id, result),
+    };
+
+    // Not one of ours, e.g. Steam updating something in the background
+    let Some(index) = mod_queue
+        .iter()
+        .position(|entry| entry.published_file_id == id)
+    else {
+        return;
+    };
+    let entry = &mut mod_queue[index];
+
+    match result {
+        Ok(_) => {
+            println!("mdq_daemon: Mod has been installed: {}", id);
+            rates.remove(&id);
+            set_step_state(step, entry, QueueState::Installed);
+            mod_queue.remove(index);
+
+            // Emit an extra event to let the frontend know the mod is installed
+            // Just in case the frontend is waiting for the download to finish
+            step.progress = Some(ModDownloadProgressEvent {
+                published_file_id: id.to_string(),
+                bytes_downloaded: 0.0,
+                bytes_total: 0.0,
+                percentage_downloaded: 100.0,
+                bytes_per_second: 0.0,
+                eta_seconds: Some(0.0),
+            });
+        }
+        // Only the active item can fail, a late error for something paused doesn't count
+        Err(reason) if entry.state.is_active() => {
+            println!("mdq_daemon: ❌ {} failed: {}", id, reason);
+            rates.remove(&id);
+            set_step_state(step, entry, QueueState::Failed(reason));
+        }
+        Err(_) => {}
+    }
+}
+
+/// Starts the next queued mod, unless one is already active. Mods that turn out to be
+/// installed already are done right away, everything else gets subscribed.
+pub fn mdq_start_next(
+    backend: &dyn SteamBackend,
+    mod_queue: &mut VecDeque<QueueEntry>,
+    sender: &mpsc::UnboundedSender<DownloadEvent>,
+    now: Instant,
+    step: &mut MdqStep,
+) {
+    // Whatever we're working on goes first
+    // P.S. Sometimes the queue is empty!
+    if mod_queue.iter().any(|entry| entry.state.is_active()) {
+        return;
+    }
+    let Some(index) = mod_queue
+        .iter()
+        .position(|entry| entry.state == QueueState::Queued)
+    else {
+        return;
+    };
+    let entry = &mut mod_queue[index];
+    let id = entry.published_file_id;
+
+    // Lets check if the mod is installed? (and up to date, unless we were told to update it)
+    let installed = backend.item_install_info(id).is_some();
+    if installed && !entry.update && !backend.item_needs_update(id) {
+        println!("mdq_daemon: Mod is already installed: {}", id);
+        set_step_state(step, entry, QueueState::Installed);
+        mod_queue.remove(index);
+        return;
+    }
+
+    // At this point we know the mod is not installed (or outdated)...
+    // Subscribing an item we already have just comes back Ok, and the download_item
+    // after it gets the update going.
+    // Lets cook that shit up 🍳
+    let sender = sender.clone();
+    backend.subscribe_item(
+        id,
+        Box::new(move |result| {
+            let _ = sender.send(DownloadEvent::Subscribed {
+                published_file_id: id,
+                result,
+            });
+        }),
+    );
+    set_step_state(step, entry, QueueState::Subscribing);
+    entry.subscribing_since = Some(now);
+    println!("mdq_daemon: ✅ Downloading mod: {}", id);
+}
+
+/// Fails the item if Steam never answered its subscribe, otherwise a lost callback
+/// holds up the whole queue.
+pub fn mdq_expire_subscribe(
+    mod_queue: &mut VecDeque<QueueEntry>,
+    now: Instant,
+    step: &mut MdqStep,
+) {
+    for entry in mod_queue
+        .iter_mut()
+        .filter(|entry| entry.state == QueueState::Subscribing)
+    {
+        let Some(since) = entry.subscribing_since else {
+            continue;
+        };
+        if now.duration_since(since) < SUBSCRIBE_TIMEOUT {
+            continue;
         }
+
+        println!(
+            "mdq_daemon: ❌ {} never heard back from Steam",
+            entry.published_file_id
+        );
+        entry.subscribing_since = None;
+        let reason = "Timed out waiting for Steam!".to_string();
+        set_step_state(step, entry, QueueState::Failed(reason));
+    }
+}
+
+/// Samples the active download's progress, the only thing we still poll for.
+pub fn mdq_poll_progress(
+    backend: &dyn SteamBackend,
+    mod_queue: &mut VecDeque<QueueEntry>,
+    rates: &mut HashMap<u64, DownloadRate>,
+    now: Instant,
+    step: &mut MdqStep,
+) {
+    let Some(entry) = mod_queue.iter_mut().find(|entry| entry.state.is_active()) else {
+        return;
+    };
+    let id = entry.published_file_id;
+
+    // Not started yet, still waiting on Steam
+    let Some((bytes_downloaded, bytes_total)) = backend.item_download_info(id) else {
+        return;
+    };
+    set_step_state(step, entry, QueueState::Downloading);
+
+    // Gather the download info and send it to the frontend
+    let percentage_downloaded = if bytes_total > 0 {
+        bytes_downloaded as f64 / bytes_total as f64 * 100.0
+    } else {
+        0.0
+    };
+    let rate = rates.entry(id).or_default();
+    let bytes_per_second = rate.sample(now, bytes_downloaded);
+
+    println!(
+        "mdq_daemon: {} is downloading... {:.1}% ({}/{})",
+        id, percentage_downloaded, bytes_downloaded, bytes_total
+    );
+
+    step.progress = Some(ModDownloadProgressEvent {
+        published_file_id: id.to_string(),
+        bytes_downloaded: bytes_downloaded as f64,
+        bytes_total: bytes_total as f64,
+        percentage_downloaded,
+        bytes_per_second,
+        eta_seconds: rate.eta(bytes_total.saturating_sub(bytes_downloaded)),
     });
+}
 
-    // Event Emitter 👷
-    // This is responsible for keeping the frontend up to date about what mod is currently
-    // downloading and its information.
+/// Puts the active item back in line, so `mdq_start_next` looks at it again.
+/// Used when callbacks may have been missed, e.g. after a remount.
+fn mdq_requeue_active(mod_queue: &mut VecDeque<QueueEntry>, step: &mut MdqStep) {
+    for entry in mod_queue.iter_mut().filter(|entry| entry.state.is_active()) {
+        set_step_state(step, entry, QueueState::Queued);
+    }
+}
 
-    Ok(())
+fn set_step_state(step: &mut MdqStep, entry: &mut QueueEntry, state: QueueState) {
+    if entry.state == state {
+        return;
+    }
+
+    entry.state = state.clone();
+    step.states.push(ModQueueStateEvent {
+        published_file_id: entry.published_file_id.to_string(),
+        state,
+    });
 }
 
 /// Unsubscribes from a mod from the Steamworks API.
@@ -262,25 +725,24 @@ pub async fn mdq_start_daemon(app_handle: AppHandle) -> Result<(), String> {
 #[specta::specta]
 pub async fn steam_remove_mod(published_file_id: String) -> Result<(), String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
-    // Grab UGC manager & parse id
-    let ugc = client.ugc();
+    // Parse id
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
 
     // Unsub item
-    ugc.unsubscribe_item(
-        steamworks::PublishedFileId(published_file_id),
-        |i| match i {
+    backend.unsubscribe_item(
+        published_file_id,
+        Box::new(|i| match i {
             Ok(_) => println!("steam_remove_mod, mod unsubscribed successfully"),
             Err(e) => println!("Error unsubscribing mod: {}", e),
-        },
+        }),
     );
 
     Ok(())
@@ -291,34 +753,33 @@ pub async fn steam_remove_mod(published_file_id: String) -> Result<(), String> {
 #[specta::specta]
 pub async fn steam_remove_mod_forcefully(published_file_id: String) -> Result<(), String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
-    // Grab UGC manager & parse id
-    let ugc = client.ugc();
+    // Parse id
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
 
     // Unsubscribe and delete the mod
-    ugc.unsubscribe_item(
-        steamworks::PublishedFileId(published_file_id),
-        |i| match i {
+    backend.unsubscribe_item(
+        published_file_id,
+        Box::new(|i| match i {
             Ok(_) => println!("steam_remove_mod_forcefully: mod unsubscribed successfully"),
             Err(e) => println!("Error unsubscribing mod: {}", e),
-        },
+        }),
     );
-    ugc.delete_item(
-        steamworks::PublishedFileId(published_file_id),
-        |i| match i {
+    backend.delete_item(
+        published_file_id,
+        Box::new(|i| match i {
             Ok(_) => {
                 println!("steam_remove_mod_forcefully: requested deletion of mod successfully")
             }
             Err(e) => println!("Error deleting mod: {}", e),
-        },
+        }),
     );
 
     Ok(())
@@ -329,24 +790,23 @@ pub async fn steam_remove_mod_forcefully(published_file_id: String) -> Result<()
 #[specta::specta]
 pub async fn steam_fix_mod(published_file_id: String) -> Result<(), String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Check if the mod is installed before trying to download it
-    let ugc = client.ugc();
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
-    let install_info = ugc.item_install_info(steamworks::PublishedFileId(published_file_id));
+    let install_info = backend.item_install_info(published_file_id);
     if install_info.is_none() {
         return Err("Mod is not installed! I can only fix installed mods.".to_string());
     }
 
     // Try to "download" the mod again, will verify the files
-    let is_success = ugc.download_item(steamworks::PublishedFileId(published_file_id), true);
+    let is_success = backend.download_item(published_file_id, true);
 
     match is_success {
         true => Ok(()),
@@ -363,18 +823,17 @@ pub async fn steam_fix_mod_forcefully(
     app_handle: AppHandle,
 ) -> Result<(), String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Make sure the mod is installed!
-    let ugc = client.ugc();
     let published_file_id = published_file_id
         .parse::<u64>()
         .map_err(|e| e.to_string())?;
-    let install_info = ugc.item_install_info(steamworks::PublishedFileId(published_file_id));
+    let install_info = backend.item_install_info(published_file_id);
     if install_info.is_none() {
         return Err("Mod is not installed! I can only fix installed mods.".to_string());
     }
@@ -392,7 +851,7 @@ pub async fn steam_fix_mod_forcefully(
         "steam_fix_mod_forcefully: ✅ Re-downloading mod... {}",
         published_file_id
     );
-    ugc.download_item(steamworks::PublishedFileId(published_file_id), true);
+    backend.download_item(published_file_id, true);
 
     // A short task to periodically check the download status
     task::spawn(async move {
@@ -402,20 +861,26 @@ pub async fn steam_fix_mod_forcefully(
             time::sleep(Duration::from_millis(250)).await;
 
             // Get the client...
-            let client = client::get_client().await;
-            if client.is_none() {
+            let backend = client::get_backend().await;
+            if backend.is_none() {
                 continue;
             }
-            let client = client.unwrap();
-
-            // Query the download status
-            let ugc = client.ugc();
-            let download_info =
-                ugc.item_download_info(steamworks::PublishedFileId(published_file_id));
-            if download_info.is_none() {
-                panic!("⚠️ There was a serious error querying the download status!");
-            }
-            let download_info = download_info.unwrap();
+            let backend = backend.unwrap();
+
+            // Query the download status, Steam stops reporting it once the mod is installed
+            let Some(download_info) = backend.item_download_info(published_file_id) else {
+                match backend.item_install_info(published_file_id) {
+                    Some(_) => println!(
+                        "steam_fix_mod_forcefully: {} has been redownloaded!",
+                        published_file_id
+                    ),
+                    None => println!(
+                        "steam_fix_mod_forcefully: ⚠️ Couldn't query the download status of {}",
+                        published_file_id
+                    ),
+                }
+                break;
+            };
 
             // Gather the data for the active download
             let bytes_downloaded = download_info.0;
@@ -457,18 +922,17 @@ pub async fn steam_fix_mod_forcefully(
 pub async fn steam_get_missing_mods_for_server(
     required_mods: Vec<String>,
 ) -> Result<Vec<String>, String> {
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
-    let ugc = client.ugc();
+    let backend = backend.unwrap();
 
     // Check if the mods are installed
     let mut missing_mods: Vec<String> = Vec::new();
     for mod_id in required_mods {
         let id = mod_id.parse::<u64>().unwrap();
-        let install_info = ugc.item_install_info(steamworks::PublishedFileId(id));
+        let install_info = backend.item_install_info(id);
         if install_info.is_none() {
             missing_mods.push(id.to_string());
         }
@@ -477,89 +941,50 @@ pub async fn steam_get_missing_mods_for_server(
     Ok(missing_mods)
 }
 
-/// Queries the Steamworks API for all installed mods and emits the results to the frontend.
-/// Emits a "steam_get_installed_mods_result" event for each mod found, with the mod's information.
+/// Lists every installed mod, emitting a `ModInfoFoundEvent` for each and an
+/// `InstalledModsListedEvent` once done. Details come from the installed mod cache, so
+/// this is cheap to call often, and without Steam it returns the cached list.
 #[tauri::command]
 #[specta::specta]
-pub async fn steam_get_installed_mods(app_handle: AppHandle) -> Result<(), String> {
-    // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
-        return Err("No steam client found!".to_string());
-    }
-    let client = client.unwrap();
-
-    // Get the installed mods
-    let ugc = client.ugc();
-    let subscribed_items = ugc.subscribed_items();
-
-    for item in subscribed_items {
-        let extended_info = ugc.query_item(item).map_err(|e| e.to_string())?;
-
-        // We only want DOWNLOADED mods
-        let path = ugc.item_install_info(item);
-        if path.is_none() {
-            continue;
-        }
-        let path = path.unwrap().folder;
-
-        let handle = app_handle.clone();
-        extended_info.fetch(move |i| {
-            // Grab the mod info!
-            // Check for steam errors
-            let query_result = i;
-            match query_result {
-                Ok(_) => (),
-                Err(e) => {
-                    dbg!("Error fetching mod info: {}", e);
-                    return;
-                }
-            }
-            let query_result = query_result.unwrap();
-
-            // Check if found
-            let query_result = query_result.get(0);
-            match query_result {
-                Some(_) => (),
-                None => {
-                    dbg!("Error fetching mod info: No mod found!");
-                    return;
+pub async fn steam_get_installed_mods(
+    app_handle: AppHandle,
+) -> Result<Vec<ModInfoFoundEvent>, String> {
+    // Work on a copy, refreshing talks to Steam and walks folders, nobody should wait on that
+    let cache_ref = INSTALLED_MOD_CACHE.clone();
+    let cached = cache_ref.read().await.clone();
+    let mut cache = match cached {
+        Some(cache) => cache,
+        None => installed::load(&app_handle).unwrap_or_else(|e| {
+            println!("steam_get_installed_mods: Couldn't load the cache: {}", e);
+            installed::ModCache::new()
+        }),
+    };
+
+    // No steam client, the cache is all we have
+    let backend = client::get_backend().await;
+    let offline = backend.is_none();
+    let mods = match backend {
+        Some(backend) => {
+            let (mods, changed) = installed::refresh(backend.as_ref(), &mut cache).await;
+            if changed {
+                if let Err(e) = installed::save(&app_handle, &cache) {
+                    println!("steam_get_installed_mods: Couldn't save the cache: {}", e);
                 }
             }
-            let query_result = query_result.unwrap();
-            let size = get_size(&path);
-            if size.is_err() {
-                dbg!("Error fetching mod size: {}", size.err());
-                return;
-            }
-            let size = size.unwrap();
-
-            // Emit the mod info!
-            ModInfoFoundEvent {
-                published_file_id: query_result.published_file_id.0.to_string(),
-                title: query_result.title,
-                description: query_result.description,
-                owner_steam_id: query_result.owner.raw().to_string(),
-                time_created: query_result.time_created,
-                time_updated: query_result.time_updated,
-                time_added_to_user_list: query_result.time_added_to_user_list,
-                banned: query_result.banned,
-                accepted_for_use: query_result.accepted_for_use,
-                tags: query_result.tags.clone(),
-                tags_truncated: query_result.tags_truncated,
-                file_size: size as u32,
-                url: query_result.url.clone(),
-                num_upvotes: query_result.num_upvotes,
-                num_downvotes: query_result.num_downvotes,
-                score: query_result.score,
-                num_children: query_result.num_children,
-            }
-            .emit(&handle)
-            .expect("Failed to emit query result");
-        });
+            mods
+        }
+        None => installed::cached(&cache),
+    };
+    *cache_ref.write().await = Some(cache);
+
+    for info in &mods {
+        info.clone().emit(&app_handle).map_err(|e| e.to_string())?;
     }
+    InstalledModsListedEvent::new(mods.len(), offline)
+        .emit(&app_handle)
+        .map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok(mods)
 }
 
 /// Queries the Steamworks API for a specific mod's information and emits the results to the frontend.
@@ -572,46 +997,25 @@ pub async fn steam_get_mod_info(
     published_file_id: String,
 ) -> Result<(), String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
-    // Get the mod info
-    let ugc = client.ugc();
-    let published_file_id = published_file_id.parse::<u64>().unwrap();
-    let extended_info = ugc
-        .query_item(steamworks::PublishedFileId(published_file_id))
+    // Get the mod info, the callback only passes it back so errors end up here
+    let published_file_id = published_file_id
+        .parse::<u64>()
         .map_err(|e| e.to_string())?;
+    let query_result = backend::fetch_items(backend.as_ref(), vec![published_file_id])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("Mod not found!".to_string())?;
 
-    extended_info.fetch(move |i| {
-        let query_result = i.unwrap().get(0).unwrap();
-
-        ModInfoFoundEvent {
-            published_file_id: query_result.published_file_id.0.to_string(),
-            title: query_result.title,
-            description: query_result.description,
-            owner_steam_id: query_result.owner.raw().to_string(),
-            time_created: query_result.time_created,
-            time_updated: query_result.time_updated,
-            time_added_to_user_list: query_result.time_added_to_user_list,
-            banned: query_result.banned,
-            accepted_for_use: query_result.accepted_for_use,
-            tags: query_result.tags.clone(),
-            tags_truncated: query_result.tags_truncated,
-            file_size: query_result.file_size,
-            url: query_result.url.clone(),
-            num_upvotes: query_result.num_upvotes,
-            num_downvotes: query_result.num_downvotes,
-            score: query_result.score,
-            num_children: query_result.num_children,
-        }
+    ModInfoFoundEvent::from(query_result)
         .emit(&app_handle)
-        .expect("Failed to emit query result");
-    });
-
-    Ok(())
+        .map_err(|e| e.to_string())
 }
 
 /// Retrieves the current user's display name from the Steamworks API.
@@ -620,14 +1024,14 @@ pub async fn steam_get_mod_info(
 #[specta::specta]
 pub async fn steam_get_user_display_name() -> Result<String, String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Grab that name!
-    Ok(client.friends().name())
+    Ok(backend.user_name())
 }
 
 /// Retrieves the current user's Steam 64 ID from the Steamworks API.
@@ -636,14 +1040,14 @@ pub async fn steam_get_user_display_name() -> Result<String, String> {
 #[specta::specta]
 pub async fn steam_get_user_id() -> Result<String, String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Grab that ID!
-    Ok(client.user().steam_id().raw().to_string())
+    Ok(backend.user_steam_id().to_string())
 }
 
 /// Queries the Steamworks API for the current user's avatar and returns it as a byte array.
@@ -652,139 +1056,54 @@ pub async fn steam_get_user_id() -> Result<String, String> {
 #[specta::specta]
 pub async fn steam_get_user_avi() -> Result<Vec<u8>, String> {
     // Check that steam client!
-    let client = client::get_client().await;
-    if client.is_none() {
+    let backend = client::get_backend().await;
+    if backend.is_none() {
         return Err("No steam client found!".to_string());
     }
-    let client = client.unwrap();
+    let backend = backend.unwrap();
 
     // Get the avatar!
-    let avi = client.friends().medium_avatar();
+    let avi = backend.user_avatar();
     match avi {
         Some(avi) => Ok(avi),
         None => Err("No avatar found 😥".to_string()),
     }
 }
 
-/// Starts a daemon that runs Steamworks callbacks every 50ms.
-/// We can start this deamon before starting steamworks, as it will
-/// continually check if the client is available.
+/// Starts the Steam thread, which runs Steamworks callbacks every 50ms.
+/// We can start it before starting steamworks, callbacks only run once the API is mounted.
 #[tauri::command]
 #[specta::specta]
 pub async fn steam_start_daemon() -> Result<(), String> {
-    // Check if we are already running the callback daemon!
-    let is_callback_daemon_running_ref = IS_CALLBACK_DAEMON_RUNNING.clone();
-    let mut is_callback_daemon_running = is_callback_daemon_running_ref.write().await;
-    if *is_callback_daemon_running {
-        return Ok(());
-    }
-
-    task::spawn(async {
-        loop {
-            // Time to sleep before trying again
-            time::sleep(Duration::from_millis(50)).await;
-
-            if client::get_client().await.is_none() {
-                continue;
-            }
-
-            // Get the client every time?
-            // This is because the client might be dropped and recreated
-            let single = client::get_single();
-            single.run_callbacks();
-        }
-    });
-
-    *is_callback_daemon_running = true;
+    client::start();
     Ok(())
 }
 
 /// Initializes the Steamworks API with the DayZ app id.
 /// This function must be called before any other Steamworks functions.
-/// Can error if the is already mounted, or has an incorrect app id.
+/// Does nothing if the API is already mounted, can error if Steam isn't running or has an incorrect app id.
 #[tauri::command]
 #[specta::specta]
 pub async fn steam_mount_api() -> Result<(), String> {
-    // Get the base reference to the client
-    let client_ref = client::STEAM_CLIENT.clone();
-    let mut client_ref = client_ref.lock_owned().await;
-
-    // Client is already initialized
-    if client_ref.is_some() {
-        return Ok(());
-    }
-
-    // Mount API with DayZ app id
-    let result = steamworks::Client::init_app(221100);
-
-    match result {
-        Ok(client) => {
-            let (client, single) = client;
-
-            // Manually set the client and single
-            *client_ref = Some(client);
-            unsafe {
-                client::STEAM_SINGLE = Some(single);
-            }
-
-            dbg!("Steamworks API mounted successfully!");
-            Ok(())
-        }
-        Err(e) => {
-            dbg!("Error initializing Steamworks: {}", e.to_string());
-            Err(e.to_string())
-        }
-    }
+    client::request(SteamRequest::Mount).await
 }
 
 /// Destructures the Steamworks API.
 /// Does not *need* to be called, but can be useful forcing Steam
 /// to think that we have shutdown and the "game" has been closed.
 /// Can error if the is already mounted, or the user does not own the game.
-/// WARN: This function is inherently unsafe! Please use with caution.
 #[tauri::command]
 #[specta::specta]
 pub async fn steam_unmount_api() -> Result<(), String> {
-    // Get the base reference to the client
-    let client_ref = client::STEAM_CLIENT.clone();
-    let mut client_ref = client_ref.lock_owned().await;
-
-    // We don't have a client to unmount
-    if client_ref.is_none() {
-        return Ok(());
-    }
-
-    // Run some last callbacks!
-    unsafe {
-        client::STEAM_SINGLE.as_ref().unwrap().run_callbacks();
-    }
-
-    // Manually set the client and single
-    *client_ref = None;
-    unsafe {
-        client::STEAM_SINGLE = None;
-    }
-
-    // Now we nuke the API 💣
-    println!("Shutting down Steamworks API");
-    steamworks::Client::shutdown();
-
-    // Now we have to quickly remount and unmount the api to 480
-    // As well as run the callbacks, so we don't get any errors
-    let e = steamworks::Client::init_app(0);
-    // print the error form E
-    match e {
-        Err(e) => {
-            println!("Error initializing Steamworks: {}", e);
-            return Err(e.to_string());
-        }
-        Ok(_) => {}
-    }
-
-    // Naughty! 🤭
-    steamworks::Client::shutdown();
+    client::request(SteamRequest::Unmount).await
+}
 
-    Ok(())
+/// Unmounts and mounts the Steamworks API again, in one go.
+/// Useful when Steam was restarted under us.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_remount_api() -> Result<(), String> {
+    client::request(SteamRequest::Remount).await
 }
 
 /// Structure of the Steamworks Installed Mod Info
@@ -801,7 +1120,8 @@ pub struct ModInfoFoundEvent {
     accepted_for_use: bool,
     tags: Vec<String>,
     tags_truncated: bool,
-    file_size: u32,
+    /// Bytes. For installed mods it's the folder size, which can be well past 4 GiB.
+    file_size: f64,
     url: String,
     num_upvotes: u32,
     num_downvotes: u32,
@@ -809,6 +1129,30 @@ pub struct ModInfoFoundEvent {
     num_children: u32,
 }
 
+impl From<WorkshopItem> for ModInfoFoundEvent {
+    fn from(item: WorkshopItem) -> Self {
+        ModInfoFoundEvent {
+            published_file_id: item.published_file_id.to_string(),
+            title: item.title,
+            description: item.description,
+            owner_steam_id: item.owner_steam_id.to_string(),
+            time_created: item.time_created,
+            time_updated: item.time_updated,
+            time_added_to_user_list: item.time_added_to_user_list,
+            banned: item.banned,
+            accepted_for_use: item.accepted_for_use,
+            tags: item.tags,
+            tags_truncated: item.tags_truncated,
+            file_size: item.file_size as f64,
+            url: item.url,
+            num_upvotes: item.num_upvotes,
+            num_downvotes: item.num_downvotes,
+            score: item.score,
+            num_children: item.num_children,
+        }
+    }
+}
+
 /// Event for Active Download Progress
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
 pub struct ActiveDownloadProgressEvent {
@@ -817,3 +1161,368 @@ pub struct ActiveDownloadProgressEvent {
     bytes_total: String,
     percentage_downloaded: String,
 }
+
+impl From<&ModDownloadProgressEvent> for ActiveDownloadProgressEvent {
+    fn from(progress: &ModDownloadProgressEvent) -> Self {
+        ActiveDownloadProgressEvent {
+            published_file_id: progress.published_file_id.clone(),
+            bytes_downloaded: progress.bytes_downloaded.to_string(),
+            bytes_total: progress.bytes_total.to_string(),
+            percentage_downloaded: progress.percentage_downloaded.to_string(),
+        }
+    }
+}
+
+/// Emitted whenever a queued mod changes state.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
+pub struct ModQueueStateEvent {
+    published_file_id: String,
+    state: QueueState,
+}
+
+/// Progress of the active download. Byte counts are f64 so JS gets them as numbers.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
+pub struct ModDownloadProgressEvent {
+    published_file_id: String,
+    bytes_downloaded: f64,
+    bytes_total: f64,
+    percentage_downloaded: f64,
+    bytes_per_second: f64,
+    /// Seconds left at the current speed, `None` until we know the speed.
+    eta_seconds: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeItem;
+    use super::fake::FakeSteam;
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    /// How long a queue gets to finish before we call it stuck.
+    const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// The daemon's loop body without the app around it.
+    struct Daemon {
+        fake: Arc<FakeSteam>,
+        queue: VecDeque<QueueEntry>,
+        rates: HashMap<u64, DownloadRate>,
+        sender: mpsc::UnboundedSender<DownloadEvent>,
+        receiver: mpsc::UnboundedReceiver<DownloadEvent>,
+        states: Vec<(u64, QueueState)>,
+    }
+
+    impl Daemon {
+        fn new(fake: FakeSteam, ids: &[u64]) -> Daemon {
+            let fake = Arc::new(fake);
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let listener = sender.clone();
+            fake.watch_downloads(Arc::new(move |event| {
+                let _ = listener.send(event);
+            }));
+
+            Daemon {
+                fake,
+                queue: ids
+                    .iter()
+                    .map(|id| QueueEntry::new(*id, None, None))
+                    .collect(),
+                rates: HashMap::new(),
+                sender,
+                receiver,
+                states: Vec::new(),
+            }
+        }
+
+        /// Lets Steam run its callbacks, then does one pass of the daemon.
+        fn step(&mut self) {
+            self.fake.tick();
+
+            let mut step = MdqStep::default();
+            while let Ok(event) = self.receiver.try_recv() {
+                let backend: &dyn SteamBackend = self.fake.as_ref();
+                mdq_handle_event(
+                    Some(backend),
+                    &mut self.queue,
+                    &mut self.rates,
+                    event,
+                    &mut step,
+                );
+            }
+            let now = Instant::now();
+            mdq_expire_subscribe(&mut self.queue, now, &mut step);
+            mdq_start_next(
+                self.fake.as_ref(),
+                &mut self.queue,
+                &self.sender,
+                now,
+                &mut step,
+            );
+            mdq_poll_progress(
+                self.fake.as_ref(),
+                &mut self.queue,
+                &mut self.rates,
+                now,
+                &mut step,
+            );
+
+            for event in step.states {
+                let id = event.published_file_id.parse().unwrap();
+                self.states.push((id, event.state));
+            }
+        }
+
+        /// The first mod that's still active or waiting its turn.
+        fn unsettled(&self) -> Option<&QueueEntry> {
+            self.queue
+                .iter()
+                .find(|entry| entry.state.is_active() || entry.state == QueueState::Queued)
+        }
+
+        /// Steps until the queue is empty or nothing is moving anymore.
+        async fn run(&mut self) {
+            let settle = async {
+                while self.unsettled().is_some() {
+                    self.step();
+                    time::sleep(Duration::from_millis(1)).await;
+                }
+            };
+            let timed_out = time::timeout(SETTLE_TIMEOUT, settle).await.is_err();
+
+            if let (true, Some(entry)) = (timed_out, self.unsettled()) {
+                panic!(
+                    "Mod {} got stuck in {:?}",
+                    entry.published_file_id, entry.state
+                );
+            }
+        }
+
+        fn states_of(&self, id: u64) -> Vec<QueueState> {
+            self.states
+                .iter()
+                .filter(|(state_id, _)| *state_id == id)
+                .map(|(_, state)| state.clone())
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn downloads_queued_mod() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", 3 * MIB);
+
+        let mut daemon = Daemon::new(fake, &[1001]);
+        daemon.run().await;
+
+        assert!(daemon.queue.is_empty());
+        assert_eq!(
+            daemon.states_of(1001),
+            vec![
+                QueueState::Subscribing,
+                QueueState::Downloading,
+                QueueState::Installed
+            ]
+        );
+        assert!(daemon.fake.item_install_info(1001).is_some());
+    }
+
+    #[tokio::test]
+    async fn downloads_one_mod_at_a_time() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", 2 * MIB);
+        fake.add_simple_item(1002, "Mod B", 2 * MIB);
+
+        let mut daemon = Daemon::new(fake, &[1001, 1002]);
+        daemon.step();
+
+        assert_eq!(daemon.queue[0].state, QueueState::Downloading);
+        assert_eq!(daemon.queue[1].state, QueueState::Queued);
+
+        daemon.run().await;
+
+        assert!(daemon.queue.is_empty());
+        let order: Vec<u64> = daemon
+            .states
+            .iter()
+            .filter(|(_, state)| *state == QueueState::Installed)
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(order, vec![1001, 1002]);
+    }
+
+    #[test]
+    fn skips_installed_mod() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", MIB);
+        fake.install(1001);
+
+        let mut daemon = Daemon::new(fake, &[1001]);
+        daemon.step();
+
+        assert!(daemon.queue.is_empty());
+        assert_eq!(daemon.states_of(1001), vec![QueueState::Installed]);
+    }
+
+    #[tokio::test]
+    async fn updates_outdated_mod() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", 2 * MIB);
+        fake.install(1001);
+        fake.publish_update(1001);
+
+        let mut daemon = Daemon::new(fake, &[1001]);
+        daemon.run().await;
+
+        assert!(daemon.queue.is_empty());
+        assert!(daemon.states_of(1001).contains(&QueueState::Downloading));
+        assert!(!daemon.fake.item_needs_update(1001));
+    }
+
+    #[tokio::test]
+    async fn fails_missing_item_and_moves_on() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", MIB);
+        fake.add_simple_item(1002, "Mod B", MIB);
+        fake.fail_next_subscribe(1001, "File not found");
+
+        let mut daemon = Daemon::new(fake, &[1001, 1002]);
+        daemon.run().await;
+
+        assert_eq!(daemon.queue.len(), 1, "Only mod 1001 should be left");
+        assert_eq!(
+            daemon.queue[0].state,
+            QueueState::Failed("File not found".to_string()),
+            "Mod 1001 should have failed"
+        );
+        assert!(
+            daemon.fake.item_install_info(1001).is_none(),
+            "Mod 1001 shouldn't be installed"
+        );
+        assert!(
+            daemon.fake.item_install_info(1002).is_some(),
+            "Mod 1002 should be installed"
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_download_without_disk_space() {
+        let fake = FakeSteam::dayz();
+        fake.add_item(FakeItem {
+            info: WorkshopItem {
+                published_file_id: 1001,
+                title: "Mod A".to_string(),
+                ..Default::default()
+            },
+            total_bytes: 4 * MIB,
+            bytes_per_tick: MIB,
+            fail_download: Some("Not enough disk space".to_string()),
+        });
+
+        let mut daemon = Daemon::new(fake, &[1001]);
+        daemon.run().await;
+
+        let reason = "Not enough disk space".to_string();
+        assert_eq!(
+            daemon.queue[0].state,
+            QueueState::Failed(reason.clone()),
+            "Mod 1001 should have failed"
+        );
+        assert_eq!(daemon.fake.download_failure(1001), Some(reason));
+        assert!(
+            daemon.fake.item_install_info(1001).is_none(),
+            "Mod 1001 shouldn't be installed"
+        );
+    }
+
+    #[test]
+    fn fails_subscribe_that_never_answers() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", MIB);
+        fake.add_simple_item(1002, "Mod B", MIB);
+
+        // Steam never runs its callbacks, so the subscribe never comes back
+        let mut daemon = Daemon::new(fake, &[1001, 1002]);
+        let started = Instant::now();
+        let mut step = MdqStep::default();
+        mdq_start_next(
+            daemon.fake.as_ref(),
+            &mut daemon.queue,
+            &daemon.sender,
+            started,
+            &mut step,
+        );
+
+        let almost = started + SUBSCRIBE_TIMEOUT - Duration::from_secs(1);
+        mdq_expire_subscribe(&mut daemon.queue, almost, &mut step);
+        assert_eq!(
+            daemon.queue[0].state,
+            QueueState::Subscribing,
+            "Mod 1001 timed out early"
+        );
+
+        let expired = started + SUBSCRIBE_TIMEOUT;
+        mdq_expire_subscribe(&mut daemon.queue, expired, &mut step);
+        assert_eq!(
+            daemon.queue[0].state,
+            QueueState::Failed("Timed out waiting for Steam!".to_string()),
+            "Mod 1001 should have timed out"
+        );
+
+        // The next one gets its turn
+        mdq_start_next(
+            daemon.fake.as_ref(),
+            &mut daemon.queue,
+            &daemon.sender,
+            expired,
+            &mut step,
+        );
+        assert_eq!(
+            daemon.queue[1].state,
+            QueueState::Subscribing,
+            "Mod 1002 should be next"
+        );
+    }
+
+    #[test]
+    fn answered_subscribe_does_not_expire() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", 100 * MIB);
+
+        let mut daemon = Daemon::new(fake, &[1001]);
+        daemon.step();
+        daemon.step();
+        assert!(daemon.queue[0].subscribing_since.is_none());
+
+        let mut step = MdqStep::default();
+        let later = Instant::now() + SUBSCRIBE_TIMEOUT * 2;
+        mdq_expire_subscribe(&mut daemon.queue, later, &mut step);
+        assert!(
+            daemon.queue[0].state.is_active(),
+            "Mod 1001 timed out after Steam answered"
+        );
+        assert!(step.states.is_empty());
+    }
+
+    #[test]
+    fn ignores_events_for_other_items() {
+        let fake = FakeSteam::dayz();
+        fake.add_simple_item(1001, "Mod A", MIB);
+
+        let mut daemon = Daemon::new(fake, &[]);
+        let mut step = MdqStep::default();
+        mdq_handle_event(
+            None,
+            &mut daemon.queue,
+            &mut daemon.rates,
+            DownloadEvent::Downloaded {
+                published_file_id: 1001,
+                result: Ok(()),
+            },
+            &mut step,
+        );
+
+        assert!(step.states.is_empty());
+        assert!(step.progress.is_none());
+    }
+}
@@ -0,0 +1,181 @@
+//! The mod download queue's entries and where they're kept. The queue is saved to the app
+//! data dir on every change, so closing the launcher mid-download doesn't lose it.
+
+use crate::query::now_secs;
+use anyhow::Result;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::AppHandle;
+use tauri::Manager;
+
+/// How much each new speed sample counts, the rest is the running average.
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// Where a queued mod is at. Only one item is active (subscribing or downloading) at a time.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+#[serde(tag = "kind", content = "reason")]
+pub enum QueueState {
+    #[default]
+    Queued,
+    /// We asked Steam to subscribe, waiting on the download to start.
+    Subscribing,
+    Downloading,
+    Installed,
+    Failed(String),
+    /// Skipped by the daemon until resumed.
+    Paused,
+}
+
+impl QueueState {
+    /// Whether this is the item the daemon is working on.
+    pub fn is_active(&self) -> bool {
+        matches!(self, QueueState::Subscribing | QueueState::Downloading)
+    }
+}
+
+/// A mod in the download queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub published_file_id: u64,
+    /// Query address of the server the mod was queued for, if any.
+    pub server: Option<String>,
+    /// Name of the preset the mod was queued for, if any.
+    pub preset: Option<String>,
+    /// Unix timestamp (seconds) of when the mod was queued.
+    pub queued_at: i64,
+    #[serde(default)]
+    pub state: QueueState,
+    /// Queued to update an installed mod, so being installed doesn't mean it's done.
+    #[serde(default)]
+    pub update: bool,
+    /// When we asked Steam to subscribe, cleared once it answers.
+    #[serde(skip)]
+    pub subscribing_since: Option<Instant>,
+}
+
+impl QueueEntry {
+    pub fn new(published_file_id: u64, server: Option<String>, preset: Option<String>) -> Self {
+        QueueEntry {
+            published_file_id,
+            server,
+            preset,
+            queued_at: now_secs(),
+            state: QueueState::Queued,
+            update: false,
+            subscribing_since: None,
+        }
+    }
+
+    /// An entry that redownloads an installed mod.
+    pub fn update(published_file_id: u64) -> Self {
+        QueueEntry {
+            update: true,
+            ..QueueEntry::new(published_file_id, None, None)
+        }
+    }
+}
+
+/// 32 Bit Queue Entry (JS can't handle u64)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct QueueEntry32 {
+    pub published_file_id: String,
+    pub server: Option<String>,
+    pub preset: Option<String>,
+    pub queued_at: u32,
+    pub state: QueueState,
+    pub update: bool,
+}
+
+impl From<QueueEntry> for QueueEntry32 {
+    fn from(entry: QueueEntry) -> Self {
+        QueueEntry32 {
+            published_file_id: entry.published_file_id.to_string(),
+            server: entry.server,
+            preset: entry.preset,
+            queued_at: entry.queued_at as u32,
+            state: entry.state,
+            update: entry.update,
+        }
+    }
+}
+
+/// Download speed worked out from successive progress samples, smoothed so the ETA
+/// doesn't jump around.
+#[derive(Default, Debug, Clone)]
+pub struct DownloadRate {
+    last: Option<(Instant, u64)>,
+    bytes_per_second: f64,
+}
+
+impl DownloadRate {
+    /// Adds a sample and returns the current speed in bytes per second.
+    pub fn sample(&mut self, at: Instant, bytes_downloaded: u64) -> f64 {
+        match self.last {
+            // Steam restarted the download, start over
+            Some((_, last_bytes)) if bytes_downloaded < last_bytes => {
+                self.bytes_per_second = 0.0;
+            }
+            Some((last_at, last_bytes)) => {
+                let elapsed = at.duration_since(last_at).as_secs_f64();
+                if elapsed > 0.0 {
+                    let rate = (bytes_downloaded - last_bytes) as f64 / elapsed;
+                    self.bytes_per_second = if self.bytes_per_second == 0.0 {
+                        rate
+                    } else {
+                        RATE_SMOOTHING * rate + (1.0 - RATE_SMOOTHING) * self.bytes_per_second
+                    };
+                }
+            }
+            None => {}
+        }
+
+        self.last = Some((at, bytes_downloaded));
+        self.bytes_per_second
+    }
+
+    /// Seconds left for `remaining` bytes at the current speed, `None` until we have a speed.
+    pub fn eta(&self, remaining: u64) -> Option<f64> {
+        if self.bytes_per_second > 0.0 {
+            Some(remaining as f64 / self.bytes_per_second)
+        } else {
+            None
+        }
+    }
+}
+
+fn queue_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(app_handle.path().app_data_dir()?.join("mod_queue.json"))
+}
+
+/// Loads the saved queue. Missing file means an empty queue.
+/// Whatever was active last session goes back to queued, the daemon starts it over.
+pub fn load(app_handle: &AppHandle) -> Result<VecDeque<QueueEntry>> {
+    let path = queue_path(app_handle)?;
+    if !path.exists() {
+        return Ok(VecDeque::new());
+    }
+
+    let json = fs::read_to_string(path)?;
+    let mut queue: VecDeque<QueueEntry> = serde_json::from_str(&json)?;
+    for entry in queue.iter_mut() {
+        if entry.state.is_active() {
+            entry.state = QueueState::Queued;
+        }
+    }
+
+    Ok(queue)
+}
+
+pub fn save(app_handle: &AppHandle, queue: &VecDeque<QueueEntry>) -> Result<()> {
+    let path = queue_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, serde_json::to_string(queue)?)?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+//! Keeps an eye on the Steam connection. When Steam exits or the user logs out, the mounted
+//! client goes stale, so we tell the frontend, pause the mod daemon, and remount with backoff
+//! until Steam comes back.
+
+use super::client;
+use super::client::SteamRequest;
+use lazy_static::lazy_static;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_specta::Event;
+use tokio::sync::RwLock;
+use tokio::{task, time};
+
+/// How often we check on Steam while it's connected.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// First wait between remount attempts, doubled after every failure.
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize, specta::Type)]
+pub enum SteamStatus {
+    Connected,
+    Disconnected,
+    Reconnecting,
+}
+
+/// Emitted whenever the Steam connection changes state.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, specta::Type, tauri_specta::Event)]
+pub struct SteamStatusEvent {
+    status: SteamStatus,
+    /// Remount attempts since Steam went away, 0 while connected.
+    attempt: u32,
+    /// Why the last remount failed, if it did.
+    error: Option<String>,
+}
+
+lazy_static! {
+    static ref IS_SUPERVISOR_RUNNING: Arc<RwLock<bool>> = Arc::new(RwLock::new(false));
+    /// Last status we saw, `None` until the supervisor has checked once.
+    static ref STEAM_STATUS: Arc<RwLock<Option<SteamStatus>>> = Arc::new(RwLock::new(None));
+}
+
+/// Whether anything talking to Steam should hold off, i.e. Steam went away and we haven't
+/// got it back yet. Never true if the supervisor isn't running.
+pub async fn is_paused() -> bool {
+    let steam_status_ref = STEAM_STATUS.clone();
+    let status = *steam_status_ref.read().await;
+    matches!(
+        status,
+        Some(SteamStatus::Disconnected) | Some(SteamStatus::Reconnecting)
+    )
+}
+
+/// Returns the last known Steam status. Errors if the supervisor hasn't checked yet.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_get_status() -> Result<SteamStatus, String> {
+    let steam_status_ref = STEAM_STATUS.clone();
+    let status = *steam_status_ref.read().await;
+    status.ok_or("Steam status is not known yet!".to_string())
+}
+
+/// Starts the Steam supervisor. It checks on Steam every couple of seconds once the API has
+/// been mounted, emits a `SteamStatusEvent` when the connection changes, and remounts with
+/// backoff when Steam goes away. Explicit unmounts are left alone.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_start_supervisor(app_handle: AppHandle) -> Result<(), String> {
+    let is_supervisor_running_ref = IS_SUPERVISOR_RUNNING.clone();
+    let mut is_supervisor_running = is_supervisor_running_ref.write().await;
+    if *is_supervisor_running {
+        return Ok(());
+    }
+
+    *is_supervisor_running = true;
+
+    task::spawn(async move {
+        let handle = app_handle.clone();
+        let mut attempt: u32 = 0;
+
+        loop {
+            // We were unmounted on purpose (e.g. DayZ is running), nothing to watch
+            if !client::is_mount_wanted() {
+                attempt = 0;
+                set_status(&handle, None, 0, None).await;
+                time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+
+            let backend = client::get_backend().await;
+            if backend.is_some_and(|backend| backend.is_alive()) {
+                attempt = 0;
+                set_status(&handle, Some(SteamStatus::Connected), 0, None).await;
+                time::sleep(CHECK_INTERVAL).await;
+                continue;
+            }
+
+            // Steam went away (or never mounted in the first place), lets get it back
+            if attempt == 0 {
+                println!("steam_supervisor: Steam went away, pausing until it's back");
+                set_status(&handle, Some(SteamStatus::Disconnected), 0, None).await;
+            }
+
+            attempt += 1;
+            set_status(&handle, Some(SteamStatus::Reconnecting), attempt, None).await;
+
+            let result = client::request(SteamRequest::Reconnect).await;
+            let alive = client::get_backend()
+                .await
+                .is_some_and(|backend| backend.is_alive());
+
+            match result {
+                Ok(_) if alive => {
+                    println!(
+                        "steam_supervisor: ✅ Remounted after {} attempt(s)",
+                        attempt
+                    );
+                    attempt = 0;
+                    set_status(&handle, Some(SteamStatus::Connected), 0, None).await;
+                    time::sleep(CHECK_INTERVAL).await;
+                }
+                result => {
+                    let error = result.err();
+                    println!(
+                        "steam_supervisor: Remount attempt {} failed: {:?}",
+                        attempt, error
+                    );
+                    set_status(&handle, Some(SteamStatus::Disconnected), attempt, error).await;
+                    time::sleep(backoff(attempt)).await;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Wait before the next remount attempt, doubling up to a minute.
+fn backoff(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    MIN_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// Stores the new status and emits an event if it changed. Retries always emit, so the
+/// frontend can show the attempt count.
+async fn set_status(
+    app_handle: &AppHandle,
+    status: Option<SteamStatus>,
+    attempt: u32,
+    error: Option<String>,
+) {
+    let steam_status_ref = STEAM_STATUS.clone();
+    let mut current = steam_status_ref.write().await;
+    let changed = *current != status;
+    *current = status;
+    drop(current);
+
+    let Some(status) = status else {
+        return;
+    };
+    if !changed && attempt == 0 {
+        return;
+    }
+
+    SteamStatusEvent {
+        status,
+        attempt,
+        error,
+    }
+    .emit(app_handle)
+    .expect("Failed to emit event!");
+}
@@ -0,0 +1,135 @@
+//! Finding installed workshop mods that are out of date, and queueing them for an update.
+//! Steam flags some of them itself (NeedsUpdate), the rest we catch by comparing the
+//! workshop's `time_updated` with the installed version's timestamp.
+
+use super::backend::fetch_items;
+use super::backend::InstallInfo;
+use super::backend::SteamBackend;
+use super::backend::QUERY_PAGE_SIZE;
+use super::client;
+use super::queue;
+use super::queue::QueueEntry;
+use super::MDQ_WAKE;
+use super::MOD_DOWNLOAD_QUEUE;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+/// An installed mod with a newer version on the workshop.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct OutdatedMod {
+    pub published_file_id: String,
+    /// Empty if the workshop couldn't be reached.
+    pub title: String,
+    /// Unix timestamp (seconds) of the latest version, 0 if the workshop couldn't be reached.
+    pub time_updated: u32,
+    /// Unix timestamp (seconds) of the installed version.
+    pub installed_at: u32,
+    /// Size of the installed version.
+    pub size_on_disk: f64,
+    /// Size of the latest version, what the update downloads at most.
+    pub file_size: u32,
+    /// Whether Steam itself flagged the mod as needing an update.
+    pub needs_update: bool,
+}
+
+/// Checks every installed, subscribed mod. If the workshop can't be reached we still
+/// return the ones Steam flagged.
+pub async fn find_outdated(backend: &dyn SteamBackend) -> Vec<OutdatedMod> {
+    let installed: Vec<(u64, InstallInfo)> = backend
+        .subscribed_items()
+        .into_iter()
+        .filter_map(|id| backend.item_install_info(id).map(|info| (id, info)))
+        .collect();
+
+    let ids: Vec<u64> = installed.iter().map(|(id, _)| *id).collect();
+    let mut items = HashMap::new();
+    for page in ids.chunks(QUERY_PAGE_SIZE) {
+        match fetch_items(backend, page.to_vec()).await {
+            Ok(found) => {
+                items.extend(found.into_iter().map(|item| (item.published_file_id, item)));
+            }
+            Err(e) => {
+                println!("find_outdated: Couldn't query the workshop: {}", e);
+                break;
+            }
+        }
+    }
+
+    let mut outdated = Vec::new();
+    for (id, info) in installed {
+        let item = items.get(&id);
+        let needs_update = backend.item_needs_update(id);
+        let newer = item.is_some_and(|item| item.time_updated > info.timestamp);
+        if !needs_update && !newer {
+            continue;
+        }
+
+        outdated.push(OutdatedMod {
+            published_file_id: id.to_string(),
+            title: item.map(|item| item.title.clone()).unwrap_or_default(),
+            time_updated: item.map(|item| item.time_updated).unwrap_or(0),
+            installed_at: info.timestamp,
+            size_on_disk: info.size_on_disk as f64,
+            file_size: item.map(|item| item.file_size).unwrap_or(0),
+            needs_update,
+        });
+    }
+
+    outdated
+}
+
+/// Returns every installed mod that has an update waiting.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_get_outdated_mods() -> Result<Vec<OutdatedMod>, String> {
+    // Check that steam client!
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("No steam client found!".to_string());
+    }
+    let backend = backend.unwrap();
+
+    Ok(find_outdated(backend.as_ref()).await)
+}
+
+/// Queues every outdated mod for an update through the mod daemon, which emits the usual
+/// progress and state events. Returns the ids that were queued.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_update_all_mods(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    // Check that steam client!
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("No steam client found!".to_string());
+    }
+    let backend = backend.unwrap();
+
+    let outdated = find_outdated(backend.as_ref()).await;
+
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mut mod_queue = mod_queue_ref.write().await;
+    let mut queued = Vec::new();
+    for outdated_mod in outdated {
+        let Ok(id) = outdated_mod.published_file_id.parse::<u64>() else {
+            continue;
+        };
+        if (*mod_queue)
+            .iter()
+            .any(|entry| entry.published_file_id == id)
+        {
+            continue;
+        }
+
+        (*mod_queue).push_back(QueueEntry::update(id));
+        queued.push(outdated_mod.published_file_id);
+    }
+
+    if !queued.is_empty() {
+        queue::save(&app_handle, &mod_queue).map_err(|e| e.to_string())?;
+        MDQ_WAKE.notify_one();
+    }
+
+    Ok(queued)
+}
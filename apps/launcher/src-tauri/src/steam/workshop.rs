@@ -0,0 +1,158 @@
+//! Browsing the DayZ workshop from the launcher. Steam pages "query all" results by page
+//! number, we hand that out as an opaque cursor so the frontend doesn't depend on it.
+
+use super::backend;
+use super::backend::WorkshopItem;
+use super::backend::WorkshopSearch;
+use super::backend::WorkshopSort;
+use super::backend::QUERY_PAGE_SIZE;
+use super::client;
+use super::client::DAYZ_APP_ID;
+use super::MOD_DOWNLOAD_QUEUE;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+
+/// A workshop item as shown in search results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct WorkshopListing {
+    pub published_file_id: String,
+    pub title: String,
+    pub description: String,
+    pub owner_steam_id: String,
+    pub preview_url: Option<String>,
+    pub tags: Vec<String>,
+    pub time_updated: u32,
+    /// Download size in bytes.
+    pub file_size: u32,
+    /// Steam's vote score, 0 to 1.
+    pub score: f32,
+    pub num_upvotes: u32,
+    pub num_downvotes: u32,
+    pub subscriptions: f64,
+    /// How many workshop items this one requires.
+    pub dependency_count: u32,
+    pub installed: bool,
+    pub queued: bool,
+}
+
+/// A page of workshop search results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct WorkshopResults {
+    pub items: Vec<WorkshopListing>,
+    /// Matches over all pages.
+    pub total_results: u32,
+    /// Pass this back to get the next page, `None` on the last one.
+    pub next_cursor: Option<String>,
+}
+
+/// Searches the DayZ workshop. `text` and `tags` narrow the results down (items need every
+/// tag), `cursor` is the `next_cursor` of the previous page, or `None` for the first one.
+/// A cursor past the last page just comes back empty, with no `next_cursor`.
+#[tauri::command]
+#[specta::specta]
+pub async fn steam_search_workshop(
+    text: Option<String>,
+    tags: Vec<String>,
+    sort: WorkshopSort,
+    cursor: Option<String>,
+) -> Result<WorkshopResults, String> {
+    let page = match cursor {
+        Some(cursor) => cursor
+            .parse::<u32>()
+            .ok()
+            .filter(|page| *page > 0)
+            .ok_or("Invalid cursor!".to_string())?,
+        None => 1,
+    };
+
+    // Check that steam client!
+    let backend = client::get_backend().await;
+    if backend.is_none() {
+        return Err("No steam client found!".to_string());
+    }
+    let backend = backend.unwrap();
+
+    let search = WorkshopSearch {
+        app_id: DAYZ_APP_ID,
+        text: text.filter(|text| !text.trim().is_empty()),
+        tags,
+        sort,
+        page,
+    };
+    let results = backend::search_workshop(backend.as_ref(), search).await?;
+
+    let next_cursor = next_cursor(page, results.items.len(), results.total_results);
+
+    let mod_queue_ref = MOD_DOWNLOAD_QUEUE.clone();
+    let mod_queue = mod_queue_ref.read().await;
+    let items = results
+        .items
+        .into_iter()
+        .map(|item| {
+            let id = item.published_file_id;
+            let installed = backend.item_install_info(id).is_some();
+            let queued = (*mod_queue)
+                .iter()
+                .any(|entry| entry.published_file_id == id);
+            listing(item, installed, queued)
+        })
+        .collect();
+
+    Ok(WorkshopResults {
+        items,
+        total_results: results.total_results,
+        next_cursor,
+    })
+}
+
+/// The cursor for the page after `page`, `None` once everything has been seen.
+fn next_cursor(page: u32, page_len: usize, total_results: u32) -> Option<String> {
+    let seen = (page as usize - 1) * QUERY_PAGE_SIZE + page_len;
+    match page_len > 0 && seen < total_results as usize {
+        true => Some((page + 1).to_string()),
+        false => None,
+    }
+}
+
+fn listing(item: WorkshopItem, installed: bool, queued: bool) -> WorkshopListing {
+    WorkshopListing {
+        published_file_id: item.published_file_id.to_string(),
+        title: item.title,
+        description: item.description,
+        owner_steam_id: item.owner_steam_id.to_string(),
+        preview_url: item.preview_url,
+        tags: item.tags,
+        time_updated: item.time_updated,
+        file_size: item.file_size,
+        score: item.score,
+        num_upvotes: item.num_upvotes,
+        num_downvotes: item.num_downvotes,
+        subscriptions: item.subscriptions as f64,
+        dependency_count: item.num_children,
+        installed,
+        queued,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_cursor_stops_on_the_last_page() {
+        let total = QUERY_PAGE_SIZE as u32 * 2 + 10;
+        assert_eq!(
+            next_cursor(1, QUERY_PAGE_SIZE, total),
+            Some("2".to_string())
+        );
+        assert_eq!(
+            next_cursor(2, QUERY_PAGE_SIZE, total),
+            Some("3".to_string())
+        );
+        assert_eq!(next_cursor(3, 10, total), None);
+
+        // Past the end, or nothing found at all
+        assert_eq!(next_cursor(4, 0, total), None);
+        assert_eq!(next_cursor(1, 0, 0), None);
+    }
+}
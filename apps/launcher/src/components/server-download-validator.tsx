@@ -43,7 +43,9 @@ export function ServerDownloadValidator({
       let name = server.mod_list.find((m) => m.workshop_id === mod)?.name
       if (!name) name = "Unknown Mod"
 
-      pushMod({ workshop_id: mod, name: name }).catch(console.error)
+      pushMod({ workshop_id: mod, name: name }, server.addr).catch(
+        console.error
+      )
     }
 
     navigate({ to: "/mod-manager" }).catch(console.error)
@@ -51,7 +51,9 @@ export function ServerPlayValidator({
       let name = server.mod_list.find((m) => m.workshop_id === mod)?.name
       if (!name) name = "Unknown Mod"
 
-      pushMod({ workshop_id: mod, name: name }).catch(console.error)
+      pushMod({ workshop_id: mod, name: name }, server.addr).catch(
+        console.error
+      )
     }
 
     navigate({ to: "/mod-manager" }).catch(console.error)
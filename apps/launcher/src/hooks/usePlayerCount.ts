@@ -15,7 +15,9 @@ async function fetchPlayerCount() {
   const response = await invoke<string>("fetch", {
     uri: "https://api.steampowered.com/ISteamUserStats/GetNumberOfCurrentPlayers/v1/?appid=221100",
   }).catch((e) => {
-    return Promise.reject(new Error("Failed to fetch player count: " + e))
+    return Promise.reject(
+      new Error("Failed to fetch player count: " + JSON.stringify(e))
+    )
   })
 
   return JSON.parse(response) as PlayerCount
@@ -1,5 +1,4 @@
 import { useEffect, useState } from "react"
-import { invoke } from "@tauri-apps/api/core"
 import { type Server32, commands } from "@/tauri-bindings"
 
 export function useUpdateServer(s: Server32) {
@@ -10,7 +9,7 @@ export function useUpdateServer(s: Server32) {
   // over time the cache would grow to be too large.
   useEffect(() => {
     const update = async () => {
-      const updated = await commands.getServerInfo(s)
+      const updated = await commands.getServerInfo(s, "Visible")
 
       if (updated.status === "error") {
         console.error(updated.error)
@@ -34,7 +33,7 @@ export function useUpdateServer(s: Server32) {
     )
 
     return () => {
-      void invoke("destroy_server_info_semaphore")
+      void commands.queryCancel([s.addr])
       clearTimeout(delayBeforeUpdate)
       clearInterval(intervalToUpdate)
     }
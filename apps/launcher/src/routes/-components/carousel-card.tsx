@@ -39,7 +39,7 @@ export function CarouselCard({ initServer }: { initServer: Server32 }) {
           >
             <div className="flex space-x-2">
               <Badge variant="secondary">Status</Badge>
-              {server.ping !== 99999 ? (
+              {server.ping != null ? (
                 <Badge className="bg-green-500 dark:bg-green-200">Online</Badge>
               ) : (
                 <Badge className="bg-red-400">Offline</Badge>
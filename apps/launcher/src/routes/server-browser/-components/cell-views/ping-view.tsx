@@ -12,12 +12,12 @@ export function PingView<TData>({ row }: PingViewProps<TData>) {
   return (
     <div className="">
       {updatedServer ? (
-        updatedServer.ping === 99999 ? (
+        updatedServer.ping == null ? (
           <div className="text-gray-700">Offline</div>
         ) : (
           <PingColored ping={updatedServer.ping!} />
         )
-      ) : row.getValue("Ping") === 99999 ? (
+      ) : row.getValue("Ping") == null ? (
         <div className="text-gray-700">Offline</div>
       ) : (
         <PingColored ping={row.getValue("Ping")} />
@@ -14,19 +14,19 @@ export function PlayersView<TData>({ row }: PlayersViewProps<TData>) {
   return (
     <div className="">
       {updatedServer &&
-        updatedServer.ping === 99999 &&
+        updatedServer.ping == null &&
         "0" + "/" + server.max_players}
 
       {updatedServer &&
-        updatedServer.ping !== 99999 &&
+        updatedServer.ping != null &&
         updatedServer.players + "/" + updatedServer.max_players}
 
       {!updatedServer &&
-        server.ping === 99999 &&
+        server.ping == null &&
         "0" + "/" + server.max_players}
 
       {!updatedServer &&
-        server.ping !== 99999 &&
+        server.ping != null &&
         server.players + "/" + server.max_players}
     </div>
   )
@@ -9,7 +9,11 @@ interface ModDownloadQueueState {
 
 interface ModDownloadQueueActions {
   pushFix: (mod: Mod32, force: boolean) => Promise<void>
-  pushMod: (mod: Mod32) => Promise<void>
+  pushMod: (
+    mod: Mod32,
+    server?: string | null,
+    preset?: string | null
+  ) => Promise<void>
   removeMod: (workshopId: string) => Promise<void>
   clearQueue: () => Promise<void>
 }
@@ -44,21 +48,34 @@ export const useModDownloadQueue = create<
 
         return Promise.resolve()
       },
-      pushMod: async (mod) => {
-        const res = await commands.mdqAddMod(mod.workshop_id).catch((e) => {
-          return Promise.reject(e)
-        })
+      pushMod: async (mod, server = null, preset = null) => {
+        const res = await commands
+          .mdqAddMod(mod.workshop_id, server, preset)
+          .catch((e) => {
+            return Promise.reject(e)
+          })
         if (res.status === "error") return Promise.reject(res.error)
 
+        // The backend resolves dependencies too, show everything that ended up queued
+        const queued: Mod32[] = res.data
+          .filter((m) => m.queued)
+          .map((m) => ({
+            workshop_id: m.published_file_id,
+            name:
+              m.published_file_id === mod.workshop_id
+                ? mod.name
+                : m.title || "Unknown Mod",
+          }))
+
         set((state) => {
-          if (
-            state.downloadQueue.some((m) => m.workshop_id === mod.workshop_id)
-          ) {
-            return state
-          }
+          const added = queued.filter(
+            (m) =>
+              !state.downloadQueue.some((q) => q.workshop_id === m.workshop_id)
+          )
+          if (added.length === 0) return state
 
           return {
-            downloadQueue: [...state.downloadQueue, mod],
+            downloadQueue: [...state.downloadQueue, ...added],
           }
         })
 
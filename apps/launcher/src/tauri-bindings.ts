@@ -1,7 +1,7 @@
          // This file was generated by [tauri-specta](https://github.com/oscartbeaumont/tauri-specta). Do not edit this file manually.
 
          export const commands = {
-async dayzLaunchVanilla(server: Server32) : Promise<Result<null, string>> {
+async dayzLaunchVanilla(server: Server32) : Promise<Result<null, LaunchError>> {
 try {
     return { status: "ok", data: await TAURI_INVOKE("dayz_launch_vanilla", { server }) };
 } catch (e) {
@@ -9,7 +9,7 @@ try {
     else return { status: "error", error: e  as any };
 }
 },
-async dayzLaunchModded(server: Server32) : Promise<Result<null, string>> {
+async dayzLaunchModded(server: Server32) : Promise<Result<null, LaunchError>> {
 try {
     return { status: "ok", data: await TAURI_INVOKE("dayz_launch_modded", { server }) };
 } catch (e) {
@@ -40,8 +40,43 @@ try {
 }
 },
 /**
- * Clears the mod download queue.
- * TODO: don't clear currently downloading mod
+ * Saves the password for a server, replacing any saved one.
+ * `@param: addr` - The server's query address.
+ */
+async dayzSetServerPassword(addr: string, password: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("dayz_set_server_password", { addr, password }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Forgets the saved password for a server.
+ * `@param: addr` - The server's query address.
+ */
+async dayzRemoveServerPassword(addr: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("dayz_remove_server_password", { addr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Whether we have a password saved for a server. Passwords never go back to the frontend.
+ * `@param: addr` - The server's query address.
+ */
+async dayzHasServerPassword(addr: string) : Promise<Result<boolean, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("dayz_has_server_password", { addr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Clears the mod download queue. The active download is kept, cancel it to get rid of it.
  */
 async mdqClear() : Promise<Result<null, string>> {
 try {
@@ -52,19 +87,21 @@ try {
 }
 },
 /**
- * Adds a mod to the download queue.
+ * Adds a mod to the download queue, optionally noting the server or preset it's for.
+ * Mods it requires are resolved through the workshop and queued ahead of it, skipping
+ * anything installed or queued already. Returns the whole resolved set, in download order,
+ * even when everything in it was installed or queued already.
  */
-async mdqAddMod(publishedFileId: string) : Promise<Result<null, string>> {
+async mdqAddMod(publishedFileId: string, server: string | null, preset: string | null) : Promise<Result<ResolvedMod[], string>> {
 try {
-    return { status: "ok", data: await TAURI_INVOKE("mdq_add_mod", { publishedFileId }) };
+    return { status: "ok", data: await TAURI_INVOKE("mdq_add_mod", { publishedFileId, server, preset }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
 /**
- * Removes a mod from the download queue.
- * TODO: don't clear currently downloading mod
+ * Removes a mod from the download queue. Won't touch the active download, cancel it instead.
  */
 async mdqRemoveMod(publishedFileId: string) : Promise<Result<null, string>> {
 try {
@@ -74,6 +111,53 @@ try {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Cancels a mod, active or not, and removes it from the queue.
+ * An active download is unsubscribed so Steam stops downloading it.
+ */
+async mdqCancelMod(publishedFileId: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("mdq_cancel_mod", { publishedFileId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Moves a queued mod to `index` in the queue, so it starts sooner (or later).
+ * The active download keeps going wherever it ends up.
+ */
+async mdqMoveMod(publishedFileId: string, index: number) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("mdq_move_mod", { publishedFileId, index }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Pauses a mod, the daemon skips it until it's resumed.
+ * NOTE: Steam has no per-item pause, so a download that already started may still finish in the background.
+ */
+async mdqPauseMod(publishedFileId: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("mdq_pause_mod", { publishedFileId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Resumes a paused mod, or retries a failed one.
+ */
+async mdqResumeMod(publishedFileId: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("mdq_resume_mod", { publishedFileId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Returns the progress of an active mod download. Will error if there is no active download.
  */
@@ -98,9 +182,11 @@ try {
 },
 /**
  * Starts the mod download queue daemon. This daemon will check if there are any mods in the queue
- * then download them. This daemon will run continuously until the app is closed. Handles,
- * unmounting of the steam api, and checking if the mod is already installed.
- * Emits a "mdq_active_download_info" event while a mod is downloading.
+ * then download them, one at a time. This daemon will run continuously until the app is closed.
+ * It's driven by Steam's subscribe, download and install callbacks, and only polls for progress.
+ * Handles unmounting of the steam api, and checking if the mod is already installed.
+ * Emits a `ModQueueStateEvent` on every state change and a `ModDownloadProgressEvent`
+ * (plus the older `ActiveDownloadProgressEvent`) while a mod is downloading.
  */
 async mdqStartDaemon() : Promise<Result<null, string>> {
 try {
@@ -110,6 +196,17 @@ try {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Lists everything in the download queue, in the order it'll be downloaded.
+ */
+async mdqList() : Promise<Result<QueueEntry32[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("mdq_list") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * Unsubscribes from a mod from the Steamworks API.
  */
@@ -169,10 +266,11 @@ try {
 }
 },
 /**
- * Queries the Steamworks API for all installed mods and emits the results to the frontend.
- * Emits a "steam_get_installed_mods_result" event for each mod found, with the mod's information.
+ * Lists every installed mod, emitting a `ModInfoFoundEvent` for each and an
+ * `InstalledModsListedEvent` once done. Details come from the installed mod cache, so
+ * this is cheap to call often, and without Steam it returns the cached list.
  */
-async steamGetInstalledMods() : Promise<Result<null, string>> {
+async steamGetInstalledMods() : Promise<Result<ModInfoFoundEvent[], string>> {
 try {
     return { status: "ok", data: await TAURI_INVOKE("steam_get_installed_mods") };
 } catch (e) {
@@ -230,9 +328,8 @@ try {
 }
 },
 /**
- * Starts a daemon that runs Steamworks callbacks every 50ms.
- * We can start this deamon before starting steamworks, as it will
- * continually check if the client is available.
+ * Starts the Steam thread, which runs Steamworks callbacks every 50ms.
+ * We can start it before starting steamworks, callbacks only run once the API is mounted.
  */
 async steamStartDaemon() : Promise<Result<null, string>> {
 try {
@@ -245,7 +342,7 @@ try {
 /**
  * Initializes the Steamworks API with the DayZ app id.
  * This function must be called before any other Steamworks functions.
- * Can error if the is already mounted, or has an incorrect app id.
+ * Does nothing if the API is already mounted, can error if Steam isn't running or has an incorrect app id.
  */
 async steamMountApi() : Promise<Result<null, string>> {
 try {
@@ -260,7 +357,6 @@ try {
  * Does not *need* to be called, but can be useful forcing Steam
  * to think that we have shutdown and the "game" has been closed.
  * Can error if the is already mounted, or the user does not own the game.
- * WARN: This function is inherently unsafe! Please use with caution.
  */
 async steamUnmountApi() : Promise<Result<null, string>> {
 try {
@@ -270,15 +366,264 @@ try {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * Unmounts and mounts the Steamworks API again, in one go.
+ * Useful when Steam was restarted under us.
+ */
+async steamRemountApi() : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_remount_api") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts the Steam supervisor. It checks on Steam every couple of seconds once the API has
+ * been mounted, emits a `SteamStatusEvent` when the connection changes, and remounts with
+ * backoff when Steam goes away. Explicit unmounts are left alone.
+ */
+async steamStartSupervisor() : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_start_supervisor") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the last known Steam status. Errors if the supervisor hasn't checked yet.
+ */
+async steamGetStatus() : Promise<Result<SteamStatus, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_get_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns every installed mod that has an update waiting.
+ */
+async steamGetOutdatedMods() : Promise<Result<OutdatedMod[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_get_outdated_mods") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Queues every outdated mod for an update through the mod daemon, which emits the usual
+ * progress and state events. Returns the ids that were queued.
+ */
+async steamUpdateAllMods() : Promise<Result<string[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_update_all_mods") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Searches the DayZ workshop. `text` and `tags` narrow the results down (items need every
+ * tag), `cursor` is the `next_cursor` of the previous page, or `None` for the first one.
+ * A cursor past the last page just comes back empty, with no `next_cursor`.
+ */
+async steamSearchWorkshop(text: string | null, tags: string[], sort: WorkshopSort, cursor: string | null) : Promise<Result<WorkshopResults, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("steam_search_workshop", { text, tags, sort, cursor }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 /**
  * This function is called to get server information.
  * We query the server and return the server information.
  * `@param: server` - The server to query.
- * TODO : Add error handling to unwraps, what if we cant get a new client?
+ * `@param: priority` - Where this query sits in line, visible rows go first.
+ */
+async getServerInfo(server: Server32, priority: QueryPriority) : Promise<Result<Server32, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("get_server_info", { server, priority }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Queries A2S info, rules and players at the same time and returns whatever came back.
+ * `@param: addr` - The server's query address, `ip:query_port`.
+ */
+async getServerDetails(addr: string) : Promise<Result<ServerDetails, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("get_server_details", { addr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Adds a server by `host:queryport`. The host can be a domain name, we resolve it
+ * and make sure something is actually answering A2S before we save it.
+ * Returns the new server entry, filled in from A2S info, EDF and rules.
+ */
+async queryAddCustomServer(host: string) : Promise<Result<Server32, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_add_custom_server", { host }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Removes a custom server. Does nothing if the server was never added.
+ */
+async queryRemoveCustomServer(addr: string) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_remove_custom_server", { addr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Lists every custom server, with the latest info we have for them.
+ */
+async queryListCustomServers() : Promise<Result<Server32[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_list_custom_servers") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Broadcasts A2S_INFO on the local subnets and returns every DayZ server that answered.
+ * Discovered servers are marked as LAN and added to the server map, so they can be
+ * refreshed and joined like any other server.
+ * `@param: window_ms` - How long to collect replies for, defaults to 1.5 seconds.
+ */
+async queryDiscoverLan(windowMs: number | null) : Promise<Result<Server32[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_discover_lan", { windowMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async queryGetSettings() : Promise<Result<QuerySettings, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_get_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async querySetSettings(settings: QuerySettings) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_set_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns servers from the server map that match the location filter, sorted as asked.
+ */
+async queryFilterByLocation(filter: LocationFilter) : Promise<Result<Server32[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_filter_by_location", { filter }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Measures a server with `samples` spaced probes and stores the result on the server.
+ * `@param: addr` - The server's query address.
+ * `@param: samples` - How many probes to send, defaults to 5, at most 20.
+ */
+async queryMeasurePing(addr: string, samples: number | null) : Promise<Result<PingStats32, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_measure_ping", { addr, samples }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets which servers background mode measures, usually the user's favorites.
+ */
+async querySetPingFavorites(addrs: string[]) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_set_ping_favorites", { addrs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts the background ping watcher, safe to call more than once.
+ * It only measures anything while `background_ping` is on in the query settings.
+ */
+async queryStartPingWatcher() : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_start_ping_watcher") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the diffs from the last few refreshes, oldest first.
+ */
+async queryGetRecentDiffs() : Promise<Result<ServerListDiffEvent[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("query_get_recent_diffs") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Sets the alert rules for a favorite, replacing any existing ones.
+ * An empty rule list stops watching the server.
+ */
+async alertsSetFavorite(alerts: FavoriteAlerts) : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("alerts_set_favorite", { alerts }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stops watching a favorite, e.g. when it gets unfavorited.
  */
-async getServerInfo(server: Server32) : Promise<Result<Server32, string>> {
+async alertsRemoveFavorite(addr: string) : Promise<Result<null, string>> {
 try {
-    return { status: "ok", data: await TAURI_INVOKE("get_server_info", { server }) };
+    return { status: "ok", data: await TAURI_INVOKE("alerts_remove_favorite", { addr }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async alertsListFavorites() : Promise<Result<FavoriteAlerts[], string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("alerts_list_favorites") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Starts the favorite watcher, safe to call more than once.
+ */
+async alertsStartWatcher() : Promise<Result<null, string>> {
+try {
+    return { status: "ok", data: await TAURI_INVOKE("alerts_start_watcher") };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -300,36 +645,34 @@ try {
 }
 },
 /**
- * This function is called to destroy the server info semaphore.
- * We do this to clear the waiting list of permits, or to change the limit.
- * NOTE: Eventually we will want to let users pick how many servers to query at once.
+ * Sets the max number of concurrent server queries from the frontend.
+ * The scheduler may run fewer than this while it is backing off from timeouts.
  */
-async updateServerInfoSemaphore(maxUpdates: number) : Promise<Result<null, string>> {
+async querySetMaxConcurrency(maxUpdates: number) : Promise<Result<null, string>> {
 try {
-    return { status: "ok", data: await TAURI_INVOKE("update_server_info_semaphore", { maxUpdates }) };
+    return { status: "ok", data: await TAURI_INVOKE("query_set_max_concurrency", { maxUpdates }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
 /**
- * This function is called to destroy the server info semaphore.
- * We do this to clear the waiting list of permits, or to change the limit.
- * NOTE: Eventually we will want to let users pick how many servers to query at once.
+ * Cancels any queued or running queries for the given servers.
+ * The frontend calls this when rows scroll out of view.
  */
-async destroyServerInfoSemaphore() : Promise<Result<null, string>> {
+async queryCancel(addrs: string[]) : Promise<Result<null, string>> {
 try {
-    return { status: "ok", data: await TAURI_INVOKE("destroy_server_info_semaphore") };
+    return { status: "ok", data: await TAURI_INVOKE("query_cancel", { addrs }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
 /**
- * This function is used to fetch data from a URI.
- * We do this here to avoid CORS issues.
+ * Fetches a URI for the webview and returns the body.
+ * Serves the cached copy when the host says nothing changed, or when we can't reach it.
  */
-async fetch(uri: string) : Promise<Result<string, string>> {
+async fetch(uri: string) : Promise<Result<string, FetchError>> {
 try {
     return { status: "ok", data: await TAURI_INVOKE("fetch", { uri }) };
 } catch (e) {
@@ -345,11 +688,23 @@ return await TAURI_INVOKE("check_for_updates");
 export const events = __makeEvents__<{
 activeDownloadProgressEvent: ActiveDownloadProgressEvent,
 dayzShutdownEvent: DayzShutdownEvent,
-modInfoFoundEvent: ModInfoFoundEvent
+favoriteAlertEvent: FavoriteAlertEvent,
+installedModsListedEvent: InstalledModsListedEvent,
+modDownloadProgressEvent: ModDownloadProgressEvent,
+modInfoFoundEvent: ModInfoFoundEvent,
+modQueueStateEvent: ModQueueStateEvent,
+serverListDiffEvent: ServerListDiffEvent,
+steamStatusEvent: SteamStatusEvent
 }>({
 activeDownloadProgressEvent: "active-download-progress-event",
 dayzShutdownEvent: "dayz-shutdown-event",
-modInfoFoundEvent: "mod-info-found-event"
+favoriteAlertEvent: "favorite-alert-event",
+installedModsListedEvent: "installed-mods-listed-event",
+modDownloadProgressEvent: "mod-download-progress-event",
+modInfoFoundEvent: "mod-info-found-event",
+modQueueStateEvent: "mod-queue-state-event",
+serverListDiffEvent: "server-list-diff-event",
+steamStatusEvent: "steam-status-event"
 })
 
 /** user-defined types **/
@@ -358,20 +713,443 @@ modInfoFoundEvent: "mod-info-found-event"
  * Event for Active Download Progress
  */
 export type ActiveDownloadProgressEvent = { published_file_id: string; bytes_downloaded: string; bytes_total: string; percentage_downloaded: string }
+/**
+ * Something worth telling the user about a favorite.
+ */
+export type AlertRule = 
+/**
+ * Player count dropped below N, i.e. a slot opened up.
+ */
+{ PlayersBelow: number } | 
+/**
+ * The server answers again after being unreachable.
+ */
+"BackOnline" | 
+/**
+ * The server version changed, usually a wipe or game update.
+ */
+"VersionChanged" | 
+/**
+ * The server's mod list changed.
+ */
+"ModsUpdated"
+export type Coordinates = { latitude: number; longitude: number }
+/**
+ * Where a player count came from.
+ */
+export type CountSource = 
+/**
+ * The FTL API snapshot, can be a few minutes old.
+ */
+"Api" | 
+/**
+ * Straight from the server's A2S_INFO response.
+ */
+"A2S" | 
+/**
+ * A2S hit the 255 cap, so the API filled in what A2S couldn't.
+ */
+"Mixed"
+/**
+ * Decoded DayZ keywords.
+ */
+export type DayzKeywords = { battleye: boolean; 
+/**
+ * Servers only tell us when third person is *disabled* (`no3rd`).
+ */
+third_person: boolean; external: boolean; private_hive: boolean; shard: string | null; modded: boolean; 
+/**
+ * Login queue size (`lqs<N>`).
+ */
+login_queue: number | null; 
+/**
+ * Day time acceleration (`etm<F>`).
+ */
+time_acceleration: number | null; 
+/**
+ * Night time acceleration (`entm<F>`).
+ */
+night_time_acceleration: number | null; 
+/**
+ * In game time of day, `HH:MM`.
+ */
+time: string | null; 
+/**
+ * Anything we don't know how to decode yet.
+ */
+unknown: string[] }
+/**
+ * Everything we could get out of a DayZ rules response.
+ */
+export type DayzRules = { mods: RuleMod[]; dlcs: RuleDlc[]; 
+/**
+ * Plain text rules, e.g. `allowedBuild`, `dedicated`, `island`...
+ */
+rules: TextRule[]; 
+/**
+ * Set if the binary blob was there but we couldn't decode it.
+ */
+decode_error: string | null }
 export type DayzShutdownEvent = null
+export type FavoriteAlertEvent = { addr: string; name: string; rule: AlertRule; message: string }
+/**
+ * Alert rules for one favorite.
+ */
+export type FavoriteAlerts = { 
+/**
+ * The server's query address.
+ */
+addr: string; 
+/**
+ * Shown in notifications, so we don't have to wait for a query to name the server.
+ */
+name: string; rules: AlertRule[] }
+/**
+ * Why a fetch failed.
+ */
+export type FetchError = { kind: "InvalidUri"; message: string } | 
+/**
+ * The host isn't on the allowlist, or the scheme isn't HTTPS.
+ */
+{ kind: "NotAllowed"; message: string } | { kind: "Timeout" } | 
+/**
+ * We couldn't reach the host and had nothing cached.
+ */
+{ kind: "Offline"; message: string } | 
+/**
+ * The host answered with an error status and we had nothing cached.
+ */
+{ kind: "Status"; message: number } | 
+/**
+ * The response was bigger than we're willing to read, in bytes.
+ */
+{ kind: "TooLarge"; message: number } | { kind: "Body"; message: string }
+/**
+ * Where a server field's current value came from.
+ */
+export type FieldSource = 
+/**
+ * The FTL API snapshot.
+ */
+"Api" | 
+/**
+ * The server's own A2S_INFO response.
+ */
+"A2S"
+/**
+ * Source of every field we reconcile.
+ */
+export type FieldSources = { gamePort: FieldSource; steamId: FieldSource; appId: FieldSource; version: FieldSource; secure: FieldSource }
+/**
+ * A2S_INFO, with the EDF fields and keywords decoded.
+ */
+export type InfoDetails = { name: string; map: string; folder: string; game: string; version: string; protocol: number; app_id: number; players: number; max_players: number; bots: number; server_type: string; os: string; password_required: boolean; vac: boolean; 
+/**
+ * EDF game port, this is the port you actually connect to.
+ */
+game_port: number | null; 
+/**
+ * EDF steam id, as a string since JS can't handle u64.
+ */
+steam_id: string | null; game_id: string | null; keywords_raw: string | null; keywords: DayzKeywords | null; ping: number }
+/**
+ * Emitted once `steam_get_installed_mods` is done, after every `ModInfoFoundEvent`.
+ */
+export type InstalledModsListedEvent = { count: number; 
+/**
+ * Whether the list came from the cache because Steam isn't there.
+ */
+offline: boolean }
+/**
+ * Why a launch failed. Tagged so the UI can tell a missing password apart from
+ * everything else and prompt for one.
+ */
+export type LaunchError = 
+/**
+ * The server wants a password and we don't have one saved. Holds the server's query address.
+ */
+{ kind: "PasswordRequired"; message: string } | { kind: "Failed"; message: string }
+/**
+ * Filters and sorting on server location.
+ */
+export type LocationFilter = { 
+/**
+ * Only servers in these countries (ISO codes), everything if empty.
+ */
+countries: string[]; 
+/**
+ * Only servers at most this far away.
+ */
+max_distance_km: number | null; sort: LocationSort | null }
+export type LocationSort = 
+/**
+ * Closest first, servers we couldn't place go last.
+ */
+"Distance" | 
+/**
+ * By country code, then closest first.
+ */
+"Country"
 /**
  * 32 Bit Mod Data Structure (JS can't handle i64)
  */
 export type Mod32 = { workshop_id: string; name: string }
+/**
+ * Progress of the active download. Byte counts are f64 so JS gets them as numbers.
+ */
+export type ModDownloadProgressEvent = { published_file_id: string; bytes_downloaded: number; bytes_total: number; percentage_downloaded: number; bytes_per_second: number; 
+/**
+ * Seconds left at the current speed, `None` until we know the speed.
+ */
+eta_seconds: number | null }
 /**
  * Structure of the Steamworks Installed Mod Info
  */
-export type ModInfoFoundEvent = { published_file_id: string; title: string; description: string; owner_steam_id: string; time_created: number; time_updated: number; time_added_to_user_list: number; banned: boolean; accepted_for_use: boolean; tags: string[]; tags_truncated: boolean; file_size: number; url: string; num_upvotes: number; num_downvotes: number; score: number; num_children: number }
+export type ModInfoFoundEvent = { published_file_id: string; title: string; description: string; owner_steam_id: string; time_created: number; time_updated: number; time_added_to_user_list: number; banned: boolean; accepted_for_use: boolean; tags: string[]; tags_truncated: boolean; 
+/**
+ * Bytes. For installed mods it's the folder size, which can be well past 4 GiB.
+ */
+file_size: number; url: string; num_upvotes: number; num_downvotes: number; score: number; num_children: number }
+/**
+ * Emitted whenever a queued mod changes state.
+ */
+export type ModQueueStateEvent = { published_file_id: string; state: QueueState }
+/**
+ * An installed mod with a newer version on the workshop.
+ */
+export type OutdatedMod = { published_file_id: string; 
+/**
+ * Empty if the workshop couldn't be reached.
+ */
+title: string; 
+/**
+ * Unix timestamp (seconds) of the latest version, 0 if the workshop couldn't be reached.
+ */
+time_updated: number; 
+/**
+ * Unix timestamp (seconds) of the installed version.
+ */
+installed_at: number; 
+/**
+ * Size of the installed version.
+ */
+size_on_disk: number; 
+/**
+ * Size of the latest version, what the update downloads at most.
+ */
+file_size: number; 
+/**
+ * Whether Steam itself flagged the mod as needing an update.
+ */
+needs_update: boolean }
+/**
+ * 32 Bit Ping Stats Data Structure (JS can't handle i64)
+ */
+export type PingStats32 = { samples: number; received: number; min_ms: number; median_ms: number; p95_ms: number; jitter_ms: number; loss_percent: number; quality: number; measured_at: number }
 export type Player = { steam_id: string; name: string; nick_name: string; avatar: number[]; is_banned: boolean }
+/**
+ * 32 Bit Player Count Data Structure (JS can't handle i64)
+ */
+export type PlayerCount32 = { players: number; max_players: number; queue: number | null; source: CountSource; updated_at: number; truncated: boolean; misreported: boolean }
+export type PlayerDetails = { name: string; score: number; 
+/**
+ * Seconds connected.
+ */
+duration: number }
+/**
+ * Priority classes for server queries, highest priority first.
+ * Visible rows always jump the line, then favorites, then everything else.
+ */
+export type QueryPriority = "Visible" | "Favorite" | "Background"
+/**
+ * User settings for what `get_server_list` hands back.
+ */
+export type QuerySettings = { 
+/**
+ * Leave out servers that are the same box as another listing.
+ */
+hide_duplicates: boolean; 
+/**
+ * Leave out servers tagged as suspicious, e.g. fake player counts.
+ */
+hide_flagged: boolean; 
+/**
+ * MaxMind format database (e.g. GeoLite2-City.mmdb) used to locate servers.
+ */
+geoip_db_path: string | null; 
+/**
+ * Where the user is, for server distances.
+ */
+user_location: Coordinates | null; 
+/**
+ * Re-measure favorites' pings in the background.
+ */
+background_ping: boolean }
+/**
+ * 32 Bit Queue Entry (JS can't handle u64)
+ */
+export type QueueEntry32 = { published_file_id: string; server: string | null; preset: string | null; queued_at: number; state: QueueState; update: boolean }
+/**
+ * Where a queued mod is at. Only one item is active (subscribing or downloading) at a time.
+ */
+export type QueueState = { kind: "Queued" } | 
+/**
+ * We asked Steam to subscribe, waiting on the download to start.
+ */
+{ kind: "Subscribing" } | { kind: "Downloading" } | { kind: "Installed" } | { kind: "Failed"; reason: string } | 
+/**
+ * Skipped by the daemon until resumed.
+ */
+{ kind: "Paused" }
+/**
+ * A mod in a resolved set, as shown to the user before (or while) it downloads.
+ */
+export type ResolvedMod = { published_file_id: string; title: string; 
+/**
+ * Direct dependencies.
+ */
+dependencies: string[]; installed: boolean; 
+/**
+ * In the download queue, either just now or from before.
+ */
+queued: boolean; in_cycle: boolean }
+export type RuleDlc = { 
+/**
+ * The DLC's bit in the server's DLC flags.
+ */
+flag: number; hash: string }
+export type RuleMod = { workshop_id: string; name: string; 
+/**
+ * Mod hash as hex, lets us tell if a local copy matches what the server runs.
+ */
+hash: string; is_dlc: boolean }
 /**
  * 32 Bit Server Data Structure (JS can't handle i64)
  */
-export type Server32 = { addr: string; game_port: number; steam_id: string; name: string; app_id: string; game_dir: string; version: string; product: string; region: number; players: number; max_players: number; bots: number; map: string; secure: boolean; dedicated: boolean; os: string; game_type: string; mod_list?: Mod32[] | null; ping?: number | null }
+export type Server32 = { addr: string; game_port: number; steam_id: string; name: string; app_id: string; game_dir: string; version: string; product: string; region: number; players: number; max_players: number; bots: number; map: string; secure: boolean; dedicated: boolean; os: string; game_type: string; mod_list?: Mod32[] | null; ping?: number | null; player_count?: PlayerCount32 | null; health?: ServerHealth32; custom?: boolean; lan?: boolean; password_required?: boolean; sources?: FieldSources; mismatches?: ServerField[]; duplicate_of?: string | null; flags?: SuspicionFlag[]; location?: ServerLocation | null; ping_stats?: PingStats32 | null }
+/**
+ * One thing that changed about one server.
+ */
+export type ServerChange = { kind: "Added"; addr: string; name: string } | { kind: "Removed"; addr: string; name: string } | { kind: "VersionChanged"; addr: string; name: string; from: string; to: string } | { kind: "MapChanged"; addr: string; name: string; from: string; to: string } | 
+/**
+ * Workshop ids, as strings since JS can't handle i64.
+ */
+{ kind: "ModsChanged"; addr: string; name: string; added: string[]; removed: string[] }
+/**
+ * Everything we can find out about a server over A2S.
+ * Each part is queried on its own, so one failing doesn't throw away the others.
+ */
+export type ServerDetails = { addr: string; info: InfoDetails | null; info_error: string | null; rules: DayzRules | null; rules_error: string | null; players: PlayerDetails[] | null; players_error: string | null }
+/**
+ * Server fields that A2S can correct.
+ */
+export type ServerField = "GamePort" | "SteamId" | "AppId" | "Version" | "Secure"
+/**
+ * 32 Bit Server Health Data Structure (JS can't handle i64)
+ */
+export type ServerHealth32 = { status: ServerStatus; last_success: number | null; last_attempt: number | null; consecutive_failures: number; next_retry_at: number | null }
+/**
+ * Everything that changed in one refresh. Emitted once the refresh is done.
+ */
+export type ServerListDiffEvent = { refreshed_at: number; changes: ServerChange[] }
+/**
+ * Where a server is, as far as the GeoIP database knows.
+ */
+export type ServerLocation = { 
+/**
+ * ISO 3166-1 alpha-2, e.g. `DE`.
+ */
+countryCode: string | null; country: string | null; city: string | null; latitude: number | null; longitude: number | null; 
+/**
+ * Great circle distance from the user's configured location.
+ */
+distanceKm: number | null }
+/**
+ * Whether we can reach a server, and how the last query went.
+ */
+export type ServerStatus = 
+/**
+ * We haven't tried yet.
+ */
+"NeverQueried" | 
+/**
+ * Last query came back fine.
+ */
+"Online" | 
+/**
+ * Last query never got a response.
+ */
+"Timeout" | 
+/**
+ * The server (or something in the way) actively refused us.
+ */
+"Refused" | 
+/**
+ * We got a response, but it wasn't valid A2S.
+ */
+"Malformed"
+export type SteamStatus = "Connected" | "Disconnected" | "Reconnecting"
+/**
+ * Emitted whenever the Steam connection changes state.
+ */
+export type SteamStatusEvent = { status: SteamStatus; 
+/**
+ * Remount attempts since Steam went away, 0 while connected.
+ */
+attempt: number; 
+/**
+ * Why the last remount failed, if it did.
+ */
+error: string | null }
+/**
+ * Why a server looks suspicious.
+ */
+export type SuspicionFlag = 
+/**
+ * Reports more players than it has slots.
+ */
+"PlayersOverMax" | 
+/**
+ * Reports the exact same non zero player count for hours.
+ */
+"FrozenCount" | 
+/**
+ * Reports far more players than it lists in A2S_PLAYER.
+ */
+"PhantomPlayers"
+export type TextRule = { name: string; value: string }
+/**
+ * A workshop item as shown in search results.
+ */
+export type WorkshopListing = { published_file_id: string; title: string; description: string; owner_steam_id: string; preview_url: string | null; tags: string[]; time_updated: number; 
+/**
+ * Download size in bytes.
+ */
+file_size: number; 
+/**
+ * Steam's vote score, 0 to 1.
+ */
+score: number; num_upvotes: number; num_downvotes: number; subscriptions: number; 
+/**
+ * How many workshop items this one requires.
+ */
+dependency_count: number; installed: boolean; queued: boolean }
+/**
+ * A page of workshop search results.
+ */
+export type WorkshopResults = { items: WorkshopListing[]; 
+/**
+ * Matches over all pages.
+ */
+total_results: number; 
+/**
+ * Pass this back to get the next page, `None` on the last one.
+ */
+next_cursor: string | null }
+/**
+ * How workshop search results are ordered.
+ */
+export type WorkshopSort = "Trending" | "MostSubscribed" | "RecentlyUpdated"
 
 /** tauri-specta globals **/
 
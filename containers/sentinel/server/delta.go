@@ -0,0 +1,159 @@
+package server
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"reflect"
+	"sort"
+	"strconv"
+	"strings"
+	"sync"
+	"unsafe"
+
+	"ftl-api/sentinel"
+)
+
+// How many revisions of the server map we keep around to hand out deltas from.
+// Clients that are further behind get a 404 and fall back to the full map.
+const maxSnapshots = 16
+
+type snapshot struct {
+	revision  string
+	serverMap map[string]FTLAPIServer
+}
+
+var (
+	snapshots      []snapshot
+	snapshotsMutex sync.Mutex
+)
+
+/*
+* Function: currentSnapshot
+* --------------------
+* Copies the sentinel's server map and tags it with a revision, the hash of its JSON.
+* Every new revision is remembered so later delta requests can start from it.
+**/
+func currentSnapshot() (snapshot, error) {
+	sentinel.ServersMutex.RLock()
+	serverMap := make(map[string]FTLAPIServer, len(sentinel.Servers.ServerMap))
+	for addr, server := range sentinel.Servers.ServerMap {
+		// The structs are identical so this is safe, see dayzServers
+		serverMap[addr] = *(*FTLAPIServer)(unsafe.Pointer(&server))
+	}
+	sentinel.ServersMutex.RUnlock()
+
+	// Map keys are marshalled in order, so the same map always hashes the same
+	data, err := json.Marshal(serverMap)
+	if err != nil {
+		return snapshot{}, err
+	}
+	hash := sha256.Sum256(data)
+	current := snapshot{revision: hex.EncodeToString(hash[:16]), serverMap: serverMap}
+
+	snapshotsMutex.Lock()
+	defer snapshotsMutex.Unlock()
+	if len(snapshots) == 0 || snapshots[len(snapshots)-1].revision != current.revision {
+		snapshots = append(snapshots, current)
+		if len(snapshots) > maxSnapshots {
+			snapshots = snapshots[len(snapshots)-maxSnapshots:]
+		}
+	}
+
+	return current, nil
+}
+
+func findSnapshot(revision string) (snapshot, bool) {
+	snapshotsMutex.Lock()
+	defer snapshotsMutex.Unlock()
+	for _, s := range snapshots {
+		if s.revision == revision {
+			return s, true
+		}
+	}
+
+	return snapshot{}, false
+}
+
+/*
+* Function: diffSnapshots
+* --------------------
+* Everything that changed going from one revision to the other, with the checksum
+* the launcher verifies the result against.
+**/
+func diffSnapshots(from snapshot, to snapshot) FTLAPIServerDelta {
+	delta := FTLAPIServerDelta{
+		FromRevision: from.revision,
+		Revision:     to.revision,
+		Added:        map[string]FTLAPIServer{},
+		Changed:      map[string]FTLAPIServer{},
+		Removed:      []string{},
+		Total:        len(to.serverMap),
+		Checksum:     checksum(to.serverMap),
+	}
+
+	for addr, server := range to.serverMap {
+		old, found := from.serverMap[addr]
+		if !found {
+			delta.Added[addr] = server
+		} else if !reflect.DeepEqual(old, server) {
+			delta.Changed[addr] = server
+		}
+	}
+	for addr := range from.serverMap {
+		if _, found := to.serverMap[addr]; !found {
+			delta.Removed = append(delta.Removed, addr)
+		}
+	}
+
+	return delta
+}
+
+// SHA-256 (hex) of the map, one canonicalLine per server sorted by address and newline
+// joined. The launcher's checksum_of has to match it
+func checksum(serverMap map[string]FTLAPIServer) string {
+	addrs := make([]string, 0, len(serverMap))
+	for addr := range serverMap {
+		addrs = append(addrs, addr)
+	}
+	sort.Strings(addrs)
+
+	lines := make([]string, 0, len(addrs))
+	for _, addr := range addrs {
+		lines = append(lines, canonicalLine(addr, serverMap[addr]))
+	}
+
+	hash := sha256.Sum256([]byte(strings.Join(lines, "\n")))
+	return hex.EncodeToString(hash[:])
+}
+
+// The address followed by every field of the server in JSON order, tab separated.
+// Mods are workshopId:name, comma separated
+func canonicalLine(addr string, server FTLAPIServer) string {
+	mods := make([]string, 0, len(server.ModList))
+	for _, mod := range server.ModList {
+		mods = append(mods, strconv.Itoa(mod.WorkshopId)+":"+mod.Name)
+	}
+
+	return strings.Join([]string{
+		addr,
+		server.Ip,
+		strconv.Itoa(server.GamePort),
+		server.SteamId,
+		server.Name,
+		strconv.Itoa(server.AppId),
+		server.GameDir,
+		server.Version,
+		server.Product,
+		strconv.Itoa(server.Region),
+		strconv.Itoa(server.Players),
+		strconv.Itoa(server.MaxPlayers),
+		strconv.Itoa(server.Bots),
+		server.Map,
+		strconv.FormatBool(server.Secure),
+		strconv.FormatBool(server.Dedicated),
+		server.Os,
+		server.GameType,
+		strings.Join(mods, ","),
+	}, "\t")
+}
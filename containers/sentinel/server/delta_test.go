@@ -0,0 +1,69 @@
+package server
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+)
+
+func TestDiffSnapshots(t *testing.T) {
+	from := snapshot{revision: "r1", serverMap: map[string]FTLAPIServer{
+		"1.1.1.1:2303": {Ip: "1.1.1.1:2303", Players: 10},
+		"2.2.2.2:2303": {Ip: "2.2.2.2:2303"},
+		"3.3.3.3:2303": {Ip: "3.3.3.3:2303"},
+	}}
+	to := snapshot{revision: "r2", serverMap: map[string]FTLAPIServer{
+		"1.1.1.1:2303": {Ip: "1.1.1.1:2303", Players: 20},
+		"2.2.2.2:2303": {Ip: "2.2.2.2:2303"},
+		"4.4.4.4:2303": {Ip: "4.4.4.4:2303"},
+	}}
+
+	delta := diffSnapshots(from, to)
+
+	assert.Equal(t, "r1", delta.FromRevision)
+	assert.Equal(t, "r2", delta.Revision)
+	assert.Equal(t, map[string]FTLAPIServer{"4.4.4.4:2303": to.serverMap["4.4.4.4:2303"]}, delta.Added)
+	assert.Equal(t, map[string]FTLAPIServer{"1.1.1.1:2303": to.serverMap["1.1.1.1:2303"]}, delta.Changed)
+	assert.Equal(t, []string{"3.3.3.3:2303"}, delta.Removed)
+	assert.Equal(t, 3, delta.Total)
+	assert.Equal(t, checksum(to.serverMap), delta.Checksum)
+}
+
+func TestChecksumIgnoresOrder(t *testing.T) {
+	serverMap := map[string]FTLAPIServer{"2.2.2.2:2303": {}, "1.1.1.1:2303": {}}
+	assert.Equal(t, checksum(serverMap), checksum(map[string]FTLAPIServer{"1.1.1.1:2303": {}, "2.2.2.2:2303": {}}))
+}
+
+func TestChecksumCoversFields(t *testing.T) {
+	serverMap := map[string]FTLAPIServer{"1.1.1.1:2303": {Ip: "1.1.1.1:2303", Players: 10}}
+	changed := map[string]FTLAPIServer{"1.1.1.1:2303": {Ip: "1.1.1.1:2303", Players: 11}}
+	assert.NotEqual(t, checksum(serverMap), checksum(changed))
+}
+
+func TestChecksumMatchesLauncher(t *testing.T) {
+	// Same map and hash as checksum_matches_the_sentinel in the launcher
+	serverMap := map[string]FTLAPIServer{"1.1.1.1:2303": {
+		Ip:         "1.1.1.1:2303",
+		GamePort:   2302,
+		SteamId:    "90000000000000001",
+		Name:       "Test",
+		AppId:      221100,
+		GameDir:    "dayz",
+		Version:    "1.26",
+		Product:    "dayz",
+		Region:     255,
+		Players:    10,
+		MaxPlayers: 60,
+		Bots:       0,
+		Map:        "chernarusplus",
+		Secure:     true,
+		Dedicated:  true,
+		Os:         "w",
+		GameType:   "battleye",
+		ModList:    []FTLAPIMod{
+			{WorkshopId: 1559212036, Name: "CF"},
+			{WorkshopId: 1564026768, Name: "Community-Online-Tools"},
+		},
+	}}
+	assert.Equal(t, "dacbb52336808304ee5d112069e7dcbe9dec5844ddbbe0e1af0d059a9d907458", checksum(serverMap))
+}
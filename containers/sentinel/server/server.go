@@ -3,10 +3,8 @@ package server
 import (
 	"net/http"
 	"strings"
-	"unsafe"
 
 	"ftl-api/log"
-	"ftl-api/sentinel"
 	"github.com/google/go-github/v62/github"
 	"github.com/labstack/echo/v4"
 	"github.com/labstack/echo/v4/middleware"
@@ -38,6 +36,7 @@ func Start() {
 	e.Use(middleware.CORS())
 	e.Use(middleware.RateLimiter(middleware.NewRateLimiterMemoryStore(1)))
 	e.GET("/v1/dayz/servers", dayzServers)
+	e.GET("/v1/dayz/servers/delta", dayzServersDelta)
 	e.GET("/v1/releases/stable/manifest", getStableManifest)
 	e.GET("/v1/releases/nightly/manifest", getNightlyManifest)
 	log.Logger.Fatal(e.Start(":8080"))
@@ -48,18 +47,49 @@ func Start() {
 * --------------------
 * This function is responsible for returning the list of servers to the client
 * We are returning the list of servers as an array of server objects.
+* The revision doubles as the ETag, so clients that are up to date get a 304.
 **/
 func dayzServers(c echo.Context) error {
 	c.Response().Header().Set("Access-Control-Allow-Origin", "*")
 	c.Response().Header().Set("Content-Type", "application/json")
 
-	// We are using unsafe to just change tags
-	// The structs are identical so this is safe
-	sentinel.ServersMutex.RLock()
-	alias := (*FTLAPIServerList)(unsafe.Pointer(&sentinel.Servers))
-	defer sentinel.ServersMutex.RUnlock()
+	current, err := currentSnapshot()
+	if err != nil {
+		log.Logger.Error("Error building server map", "error", err)
+		return c.JSON(http.StatusInternalServerError, map[string]string{"error": err.Error()})
+	}
+
+	etag := `"` + current.revision + `"`
+	c.Response().Header().Set("ETag", etag)
+	if c.Request().Header.Get("If-None-Match") == etag {
+		return c.NoContent(http.StatusNotModified)
+	}
+
+	return c.JSON(http.StatusOK, FTLAPIServerList{ServerMap: current.serverMap, Revision: current.revision})
+}
+
+/*
+* Function: dayzServersDelta
+* --------------------
+* Returns what changed since the revision in ?since=, 404 if we don't have that
+* revision anymore so the client knows to grab the full map instead.
+**/
+func dayzServersDelta(c echo.Context) error {
+	c.Response().Header().Set("Access-Control-Allow-Origin", "*")
+	c.Response().Header().Set("Content-Type", "application/json")
+
+	from, found := findSnapshot(c.QueryParam("since"))
+	if !found {
+		return c.JSON(http.StatusNotFound, map[string]string{"error": "unknown revision"})
+	}
+
+	current, err := currentSnapshot()
+	if err != nil {
+		log.Logger.Error("Error building server map", "error", err)
+		return c.JSON(http.StatusInternalServerError, map[string]string{"error": err.Error()})
+	}
 
-	return c.JSON(http.StatusOK, alias)
+	return c.JSON(http.StatusOK, diffSnapshots(from, current))
 }
 
 func getStableManifest(c echo.Context) error {
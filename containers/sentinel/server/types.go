@@ -2,6 +2,17 @@ package server
 
 type FTLAPIServerList struct {
 	ServerMap map[string]FTLAPIServer `json:"serverMap"`
+	Revision  string                  `json:"revision,omitempty"`
+}
+
+type FTLAPIServerDelta struct {
+	FromRevision string                  `json:"fromRevision"`
+	Revision     string                  `json:"revision"`
+	Added        map[string]FTLAPIServer `json:"added"`
+	Changed      map[string]FTLAPIServer `json:"changed"`
+	Removed      []string                `json:"removed"`
+	Total        int                     `json:"total"`
+	Checksum     string                  `json:"checksum"`
 }
 
 type FTLAPIServer struct {